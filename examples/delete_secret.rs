@@ -10,7 +10,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let secret_name = env::var("SECRET_NAME").expect("Missing SECRET_NAME environment variable.");
 
     let mut client = KeyVaultClient::new(&client_id, &client_secret, &tenant_id, &keyvault_name);
-    client.delete_secret(&secret_name).await?;
+    client.delete_secret(&secret_name).await?.wait().await?;
 
     Ok(())
 }
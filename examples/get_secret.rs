@@ -12,7 +12,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = KeyVaultClient::new(&client_id, &client_secret, &tenant_id, &keyvault_name);
 
     let secret = client.get_secret(&secret_name).await?;
-    dbg!(&secret.value());
+    dbg!(&secret.value().expose());
 
     Ok(())
 }
@@ -15,7 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     client.set_secret(&secret_name, &secret_value).await?;
 
     let secret = client.get_secret(&secret_name).await?;
-    assert_eq!(secret.value(), "whatup");
+    assert_eq!(secret.value().expose(), "whatup");
 
     Ok(())
 }
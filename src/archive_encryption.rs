@@ -0,0 +1,356 @@
+use crate::{random_bytes, KeyVaultError, SecretValue};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::stream::{DecryptorBE32, EncryptorBE32};
+use chacha20poly1305::aead::{KeyInit, OsRng, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use zeroize::Zeroizing;
+
+const ENCRYPTED_ARCHIVE_FORMAT: &str = "azure-sdk-keyvault-backup-encrypted";
+const ENCRYPTED_ARCHIVE_VERSION: u32 = 1;
+const KEY_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+/// The STREAM construction uses 5 bytes of the 12 byte ChaCha20Poly1305 nonce for its counter.
+const STREAM_NONCE_LENGTH: usize = 7;
+
+// Argon2id parameters recommended by OWASP, stored in every archive so that they can be raised later.
+const ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+const ARGON2_ITERATIONS: u32 = 2;
+const ARGON2_PARALLELISM: u32 = 1;
+/// Archives asking for more than this many times the default Argon2id costs are rejected, so that reading
+/// an untrusted archive can not exhaust the memory or time of the reader.
+const ARGON2_MAX_COST_FACTOR: u32 = 8;
+
+/// How a vault backup archive is encrypted.
+///
+/// Archives are encrypted with ChaCha20Poly1305 in the STREAM construction, one record at a time, so that
+/// reordering, tampering with, or truncating an archive is detected when it is read.
+#[derive(Debug, Clone)]
+pub enum ArchiveEncryption {
+    /// A key derived from a passphrase with Argon2id.
+    Passphrase(SecretValue),
+    /// A key file holding 32 random bytes, such as one created by
+    /// [`generate_key_file`](ArchiveEncryption::generate_key_file).
+    KeyFile(PathBuf),
+}
+
+impl ArchiveEncryption {
+    /// Creates a new key file with a random key. Fails if the file already exists.
+    ///
+    /// On unix, the key file is only readable and writable by its owner.
+    pub async fn generate_key_file(path: impl AsRef<Path>) -> Result<Self, KeyVaultError> {
+        let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+        OsRng.fill_bytes(&mut *key);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = tokio::fs::OpenOptions::from(options).open(path.as_ref()).await?;
+        file.write_all(&*key).await?;
+        file.sync_all().await?;
+        Ok(Self::KeyFile(path.as_ref().to_owned()))
+    }
+
+    async fn derive_key(&self, key_derivation: &KeyDerivation) -> Result<Zeroizing<Vec<u8>>, KeyVaultError> {
+        let mut key = Zeroizing::new(vec![0u8; KEY_LENGTH]);
+        match (self, key_derivation) {
+            (
+                Self::Passphrase(passphrase),
+                KeyDerivation::Argon2id {
+                    salt,
+                    memory_kib,
+                    iterations,
+                    parallelism,
+                },
+            ) => {
+                let invalid =
+                    |e: argon2::Error| KeyVaultError::InvalidArchive(format!("invalid key derivation: {}", e));
+                if *memory_kib > ARGON2_MEMORY_KIB * ARGON2_MAX_COST_FACTOR
+                    || *iterations > ARGON2_ITERATIONS * ARGON2_MAX_COST_FACTOR
+                    || *parallelism > ARGON2_PARALLELISM * ARGON2_MAX_COST_FACTOR
+                {
+                    return Err(KeyVaultError::InvalidArchive(
+                        "the key derivation parameters exceed the supported maximum".to_owned(),
+                    ));
+                }
+                let salt = base64::decode(salt)
+                    .map_err(|_| KeyVaultError::InvalidArchive("invalid key derivation salt".to_owned()))?;
+                let params = Params::new(*memory_kib, *iterations, *parallelism, Some(KEY_LENGTH)).map_err(invalid)?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(passphrase.expose().as_bytes(), &salt, &mut key)
+                    .map_err(invalid)?;
+            }
+            (Self::KeyFile(path), KeyDerivation::KeyFile) => {
+                let key_file = Zeroizing::new(tokio::fs::read(path).await?);
+                if key_file.len() != KEY_LENGTH {
+                    return Err(KeyVaultError::InvalidArchive(format!(
+                        "the key file {} must hold exactly {} bytes",
+                        path.display(),
+                        KEY_LENGTH
+                    )));
+                }
+                key.copy_from_slice(&key_file);
+            }
+            (Self::Passphrase(_), KeyDerivation::KeyFile) => {
+                return Err(KeyVaultError::InvalidArchive(
+                    "the archive is encrypted with a key file, not a passphrase".to_owned(),
+                ))
+            }
+            (Self::KeyFile(_), KeyDerivation::Argon2id { .. }) => {
+                return Err(KeyVaultError::InvalidArchive(
+                    "the archive is encrypted with a passphrase, not a key file".to_owned(),
+                ))
+            }
+        }
+        Ok(key)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "algorithm", rename_all = "snake_case")]
+enum KeyDerivation {
+    Argon2id {
+        salt: String,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+    KeyFile,
+}
+
+/// The first line of an encrypted archive, authenticated along with every record after it.
+#[derive(Serialize, Deserialize)]
+struct EncryptionHeader {
+    format: String,
+    version: u32,
+    key_derivation: KeyDerivation,
+    nonce: String,
+}
+
+/// Encrypts the lines of an archive as it is written, each into a line of base64.
+pub(crate) struct ArchiveEncryptor {
+    encryptor: EncryptorBE32<ChaCha20Poly1305>,
+    header: String,
+}
+
+impl ArchiveEncryptor {
+    pub(crate) async fn new(encryption: &ArchiveEncryption) -> Result<Self, KeyVaultError> {
+        let key_derivation = match encryption {
+            ArchiveEncryption::Passphrase(_) => KeyDerivation::Argon2id {
+                salt: base64::encode(random_bytes(SALT_LENGTH)),
+                memory_kib: ARGON2_MEMORY_KIB,
+                iterations: ARGON2_ITERATIONS,
+                parallelism: ARGON2_PARALLELISM,
+            },
+            ArchiveEncryption::KeyFile(_) => KeyDerivation::KeyFile,
+        };
+        let key = encryption.derive_key(&key_derivation).await?;
+        let nonce = random_bytes(STREAM_NONCE_LENGTH);
+        let header = serde_json::to_string(&EncryptionHeader {
+            format: ENCRYPTED_ARCHIVE_FORMAT.to_owned(),
+            version: ENCRYPTED_ARCHIVE_VERSION,
+            key_derivation,
+            nonce: base64::encode(&nonce),
+        })
+        .unwrap();
+        Ok(Self {
+            encryptor: EncryptorBE32::from_aead(
+                ChaCha20Poly1305::new_from_slice(&key).unwrap(),
+                nonce.as_slice().into(),
+            ),
+            header,
+        })
+    }
+
+    pub(crate) fn header(&self) -> &str {
+        &self.header
+    }
+
+    pub(crate) fn encrypt_line(&mut self, line: &str) -> String {
+        let payload = Payload {
+            msg: line.as_bytes(),
+            aad: self.header.as_bytes(),
+        };
+        base64::encode(self.encryptor.encrypt_next(payload).unwrap())
+    }
+
+    /// Encrypts the last line of the archive, so that the archive can not be truncated unnoticed.
+    pub(crate) fn encrypt_last_line(self, line: &str) -> String {
+        let payload = Payload {
+            msg: line.as_bytes(),
+            aad: self.header.as_bytes(),
+        };
+        base64::encode(self.encryptor.encrypt_last(payload).unwrap())
+    }
+}
+
+/// Returns the plaintext of an archive, decrypting it if it is encrypted.
+///
+/// An archive which is encrypted can only be read with `encryption`, and an archive which is not can only
+/// be read without it, so that an encrypted archive can not be swapped for a plaintext one.
+pub(crate) async fn decrypt_archive(
+    contents: &str,
+    encryption: Option<&ArchiveEncryption>,
+) -> Result<Zeroizing<String>, KeyVaultError> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header_line = lines.next().unwrap_or_default();
+    let header = match serde_json::from_str::<EncryptionHeader>(header_line) {
+        Ok(header) if header.format == ENCRYPTED_ARCHIVE_FORMAT => Some(header),
+        _ => None,
+    };
+
+    let (header, encryption) = match (header, encryption) {
+        (None, None) => return Ok(Zeroizing::new(contents.to_owned())),
+        (Some(header), Some(encryption)) if header.version == ENCRYPTED_ARCHIVE_VERSION => (header, encryption),
+        (Some(_), Some(_)) => {
+            return Err(KeyVaultError::InvalidArchive(
+                "unsupported encrypted archive version".to_owned(),
+            ))
+        }
+        (Some(_), None) => {
+            return Err(KeyVaultError::InvalidArchive(
+                "the archive is encrypted, and requires a passphrase or key file".to_owned(),
+            ))
+        }
+        (None, Some(_)) => {
+            return Err(KeyVaultError::InvalidArchive(
+                "the archive was expected to be encrypted, but is not".to_owned(),
+            ))
+        }
+    };
+
+    let key = encryption.derive_key(&header.key_derivation).await?;
+    let nonce = base64::decode(&header.nonce)
+        .ok()
+        .filter(|nonce| nonce.len() == STREAM_NONCE_LENGTH)
+        .ok_or_else(|| KeyVaultError::InvalidArchive("invalid nonce".to_owned()))?;
+    let mut decryptor =
+        DecryptorBE32::from_aead(ChaCha20Poly1305::new_from_slice(&key).unwrap(), nonce.as_slice().into());
+
+    let failed = || {
+        KeyVaultError::InvalidArchive(
+            "the archive could not be decrypted, the key is wrong or the archive was tampered with".to_owned(),
+        )
+    };
+    let lines = lines.collect::<Vec<_>>();
+    let (last_line, lines) = lines.split_last().ok_or_else(failed)?;
+    let mut plaintext = Zeroizing::new(String::new());
+    let mut push_line = |decrypted: Vec<u8>| {
+        let decrypted = Zeroizing::new(decrypted);
+        plaintext.push_str(std::str::from_utf8(&decrypted).map_err(|_| failed())?);
+        plaintext.push('\n');
+        Ok::<_, KeyVaultError>(())
+    };
+    for line in lines {
+        let ciphertext = base64::decode(line).map_err(|_| failed())?;
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: header_line.as_bytes(),
+        };
+        push_line(decryptor.decrypt_next(payload).map_err(|_| failed())?)?;
+    }
+    let ciphertext = base64::decode(last_line).map_err(|_| failed())?;
+    let payload = Payload {
+        msg: &ciphertext,
+        aad: header_line.as_bytes(),
+    };
+    push_line(decryptor.decrypt_last(payload).map_err(|_| failed())?)?;
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn encrypt(encryption: &ArchiveEncryption, lines: &[&str]) -> Vec<String> {
+        let mut encryptor = ArchiveEncryptor::new(encryption).await.unwrap();
+        let mut archive = vec![encryptor.header().to_owned()];
+        let (last_line, lines) = lines.split_last().unwrap();
+        for line in lines {
+            archive.push(encryptor.encrypt_line(line));
+        }
+        archive.push(encryptor.encrypt_last_line(last_line));
+        archive
+    }
+
+    async fn assert_invalid(archive: &str, encryption: Option<&ArchiveEncryption>) {
+        let err = decrypt_archive(archive, encryption).await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidArchive(_)));
+    }
+
+    #[tokio::test]
+    async fn decrypt_archive_with_passphrase() {
+        let encryption = ArchiveEncryption::Passphrase("correct horse battery staple".into());
+        let archive = encrypt(&encryption, &["header", "blob", "manifest"]).await.join("\n");
+        assert!(!archive.contains("blob"));
+
+        let plaintext = decrypt_archive(&archive, Some(&encryption)).await.unwrap();
+        assert_eq!("header\nblob\nmanifest\n", plaintext.as_str());
+
+        assert_invalid(&archive, Some(&ArchiveEncryption::Passphrase("hunter2".into()))).await;
+        assert_invalid(&archive, None).await;
+    }
+
+    #[tokio::test]
+    async fn decrypt_archive_detects_tampering() {
+        let key_path = std::env::temp_dir().join(format!("azure-sdk-keyvault-test-{}.key", std::process::id()));
+        let encryption = ArchiveEncryption::generate_key_file(&key_path).await.unwrap();
+        let archive = encrypt(&encryption, &["header", "blob", "manifest"]).await;
+        assert!(decrypt_archive(&archive.join("\n"), Some(&encryption)).await.is_ok());
+
+        let truncated = archive[..3].join("\n");
+        assert_invalid(&truncated, Some(&encryption)).await;
+        let reordered = [&archive[0], &archive[2], &archive[1], &archive[3]];
+        assert_invalid(
+            &reordered.iter().map(|l| l.as_str()).collect::<Vec<_>>().join("\n"),
+            Some(&encryption),
+        )
+        .await;
+        assert_invalid("header\nblob\nmanifest\n", Some(&encryption)).await;
+
+        assert!(ArchiveEncryption::generate_key_file(&key_path).await.is_err());
+        std::fs::remove_file(&key_path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn generate_key_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let key_path = std::env::temp_dir().join(format!("azure-sdk-keyvault-test-{}-private.key", std::process::id()));
+        ArchiveEncryption::generate_key_file(&key_path).await.unwrap();
+        let mode = std::fs::metadata(&key_path).unwrap().permissions().mode();
+        std::fs::remove_file(&key_path).unwrap();
+        assert_eq!(0o600, mode & 0o777);
+    }
+
+    #[tokio::test]
+    async fn decrypt_archive_rejects_untrusted_parameters() {
+        let encryption = ArchiveEncryption::Passphrase("correct horse battery staple".into());
+        let archive = encrypt(&encryption, &["header", "blob", "manifest"]).await;
+        let mut header = serde_json::from_str::<serde_json::Value>(&archive[0]).unwrap();
+        header["key_derivation"]["memory_kib"] = (4 * 1024 * 1024).into();
+        let expensive = std::iter::once(header.to_string())
+            .chain(archive[1..].iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_invalid(&expensive, Some(&encryption)).await;
+
+        let key_path = std::env::temp_dir().join(format!("azure-sdk-keyvault-test-{}-short.key", std::process::id()));
+        std::fs::write(&key_path, b"too short").unwrap();
+        let short_key = ArchiveEncryption::KeyFile(key_path.clone());
+        let archive = serde_json::json!({
+            "format": ENCRYPTED_ARCHIVE_FORMAT,
+            "version": ENCRYPTED_ARCHIVE_VERSION,
+            "key_derivation": { "algorithm": "key_file" },
+            "nonce": base64::encode([0u8; STREAM_NONCE_LENGTH]),
+        })
+        .to_string();
+        let err = decrypt_archive(&archive, Some(&short_key)).await.unwrap_err();
+        std::fs::remove_file(&key_path).unwrap();
+        assert!(matches!(err, KeyVaultError::InvalidArchive(_)));
+    }
+}
@@ -0,0 +1,1201 @@
+pub use crate::archive_encryption::ArchiveEncryption;
+
+use crate::archive_encryption::{decrypt_archive, ArchiveEncryptor};
+use crate::report::{describe_error, SecretOutcomeStatus, VaultOperationReport, VaultProgress};
+use crate::{sha256_hex, KeyVaultClient, KeyVaultError};
+use chrono::{DateTime, Utc};
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use zeroize::Zeroizing;
+
+const ARCHIVE_FORMAT: &str = "azure-sdk-keyvault-backup";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// A secret recorded in the manifest of a vault backup archive.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct ArchivedSecret {
+    name: String,
+    enabled: bool,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+    time_created: DateTime<Utc>,
+    time_updated: DateTime<Utc>,
+    /// Hex-encoded SHA-256 digest of the backup blob.
+    blob_sha256: String,
+}
+
+/// A vault backup archive is a sequence of JSON lines: a header, one line per backup blob,
+/// and a manifest of all the archived secrets, which is written last.
+///
+/// The manifest of an incremental archive still lists every secret in the vault, but only holds the blobs
+/// of the secrets which changed since its base archive. The other blobs are found in the base archives,
+/// by their digest. Secrets deleted since the base archive are left out of the manifest, and recorded as deleted.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ArchiveRecord {
+    Header {
+        format: String,
+        version: u32,
+        vault_url: String,
+        time_created: DateTime<Utc>,
+        /// Hex-encoded SHA-256 digest of the archive this one is incremental to.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_sha256: Option<String>,
+    },
+    Blob {
+        secret_name: String,
+        value: String,
+    },
+    Manifest {
+        secrets: Vec<ArchivedSecret>,
+        /// Secrets of the base archive which no longer exist in the vault.
+        #[serde(default)]
+        deleted_secrets: Vec<String>,
+    },
+}
+
+/// A read vault backup archive, whose blobs have been verified against its manifest.
+pub(crate) struct VaultArchive {
+    sha256: String,
+    base_sha256: Option<String>,
+    blobs: HashMap<String, String>,
+    secrets: Vec<ArchivedSecret>,
+    deleted_secrets: Vec<String>,
+}
+
+/// The secrets of a chain of archives as of its last archive, and the secrets deleted along the chain.
+pub(crate) struct ArchiveChain {
+    pub(crate) secrets: Vec<(ArchivedSecret, String)>,
+    pub(crate) deleted_secrets: Vec<String>,
+}
+
+impl VaultArchive {
+    pub(crate) async fn read(path: &Path, encryption: Option<&ArchiveEncryption>) -> Result<Self, KeyVaultError> {
+        let contents = tokio::fs::read_to_string(path).await?;
+        let invalid = |reason: String| KeyVaultError::InvalidArchive(format!("{}: {}", path.display(), reason));
+        let plaintext = decrypt_archive(&contents, encryption).await.map_err(|e| match e {
+            KeyVaultError::InvalidArchive(reason) => invalid(reason),
+            e => e,
+        })?;
+
+        let mut records = plaintext
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(index, line)| {
+                serde_json::from_str::<ArchiveRecord>(line)
+                    .map_err(|e| invalid(format!("line {} could not be parsed: {}", index + 1, e)))
+            });
+        let base_sha256 = match records.next().transpose()? {
+            Some(ArchiveRecord::Header {
+                format,
+                version,
+                base_sha256,
+                ..
+            }) if format == ARCHIVE_FORMAT && version == ARCHIVE_VERSION => base_sha256,
+            _ => return Err(invalid("not a supported vault backup archive".to_owned())),
+        };
+
+        let mut blobs = HashMap::new();
+        let mut manifest = None;
+        for record in records {
+            match record? {
+                ArchiveRecord::Blob { secret_name, value } => {
+                    blobs.insert(secret_name, value);
+                }
+                ArchiveRecord::Manifest {
+                    secrets,
+                    deleted_secrets,
+                } if manifest.is_none() => manifest = Some((secrets, deleted_secrets)),
+                _ => return Err(invalid("unexpected record".to_owned())),
+            }
+        }
+        let (secrets, deleted_secrets): (Vec<ArchivedSecret>, Vec<String>) =
+            manifest.ok_or_else(|| invalid("the manifest is missing, the backup may be incomplete".to_owned()))?;
+
+        // Everything is verified before the archive is used, so a damaged archive never gets partially restored.
+        for (secret_name, blob) in &blobs {
+            match secrets.iter().find(|s| &s.name == secret_name) {
+                Some(secret) if sha256_hex(blob.as_bytes()) == secret.blob_sha256 => {}
+                _ => return Err(invalid(format!("the backup of secret {} is corrupted", secret_name))),
+            }
+        }
+        if base_sha256.is_none() {
+            if let Some(secret) = secrets.iter().find(|s| !blobs.contains_key(&s.name)) {
+                return Err(invalid(format!("the backup of secret {} is missing", secret.name)));
+            }
+        }
+
+        Ok(Self {
+            sha256: sha256_hex(contents.as_bytes()),
+            base_sha256,
+            blobs,
+            secrets,
+            deleted_secrets,
+        })
+    }
+
+    /// Reads a full archive followed by its incremental archives, in the order they were created.
+    /// Returns the secrets of the latest archive along with their blobs, and the secrets deleted along the chain
+    /// which were not created again later on.
+    pub(crate) async fn read_chain<P: AsRef<Path>>(
+        paths: &[P],
+        encryption: Option<&ArchiveEncryption>,
+    ) -> Result<ArchiveChain, KeyVaultError> {
+        let mut blobs_by_digest = HashMap::new();
+        let mut deleted_secrets = BTreeSet::new();
+        let mut latest: Option<VaultArchive> = None;
+        for path in paths {
+            let archive = Self::read(path.as_ref(), encryption).await?;
+            let expected_base = latest.as_ref().map(|a| &a.sha256);
+            if archive.base_sha256.as_ref() != expected_base {
+                return Err(KeyVaultError::InvalidArchive(format!(
+                    "{}: {}",
+                    path.as_ref().display(),
+                    match expected_base {
+                        Some(_) => "not incremental to the archive before it",
+                        None => "incremental, restore it after its base archives",
+                    }
+                )));
+            }
+            for (secret_name, blob) in &archive.blobs {
+                let secret = archive.secrets.iter().find(|s| &s.name == secret_name).unwrap();
+                blobs_by_digest.insert(secret.blob_sha256.clone(), blob.clone());
+            }
+            deleted_secrets.extend(archive.deleted_secrets.iter().cloned());
+            latest = Some(archive);
+        }
+
+        let latest = latest.ok_or_else(|| KeyVaultError::InvalidArchive("no archive to restore".to_owned()))?;
+        for secret in &latest.secrets {
+            deleted_secrets.remove(&secret.name);
+        }
+        let secrets = latest
+            .secrets
+            .into_iter()
+            .map(|secret| match blobs_by_digest.get(&secret.blob_sha256) {
+                Some(blob) => Ok((secret, blob.clone())),
+                None => Err(KeyVaultError::InvalidArchive(format!(
+                    "the backup of secret {} is missing from the archives",
+                    secret.name
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ArchiveChain {
+            secrets,
+            deleted_secrets: deleted_secrets.into_iter().collect(),
+        })
+    }
+}
+
+/// Writes an archive to a temporary file next to its destination, which is only moved into place once complete.
+struct ArchiveWriter {
+    file: tokio::fs::File,
+    partial_path: PathBuf,
+    path: PathBuf,
+    encryptor: Option<ArchiveEncryptor>,
+}
+
+impl ArchiveWriter {
+    async fn create(path: &Path, encryption: Option<&ArchiveEncryption>) -> Result<Self, KeyVaultError> {
+        let encryptor = match encryption {
+            Some(encryption) => Some(ArchiveEncryptor::new(encryption).await?),
+            None => None,
+        };
+        let mut partial_path = path.as_os_str().to_owned();
+        partial_path.push(".partial");
+        let partial_path = PathBuf::from(partial_path);
+        let mut writer = Self {
+            file: tokio::fs::File::create(&partial_path).await?,
+            partial_path,
+            path: path.to_owned(),
+            encryptor,
+        };
+        if let Some(header) = writer.encryptor.as_ref().map(|e| e.header().to_owned()) {
+            writer.write_line(header).await?;
+        }
+        Ok(writer)
+    }
+
+    async fn write_line(&mut self, mut line: String) -> Result<(), KeyVaultError> {
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    async fn write(&mut self, record: &ArchiveRecord) -> Result<(), KeyVaultError> {
+        let line = Zeroizing::new(serde_json::to_string(record).unwrap());
+        let line = match &mut self.encryptor {
+            Some(encryptor) => encryptor.encrypt_line(&line),
+            None => line.to_string(),
+        };
+        self.write_line(line).await
+    }
+
+    /// Writes the manifest, which is always the last record of an archive.
+    async fn finish(mut self, manifest: &ArchiveRecord) -> Result<(), KeyVaultError> {
+        let line = Zeroizing::new(serde_json::to_string(manifest).unwrap());
+        let line = match self.encryptor.take() {
+            Some(encryptor) => encryptor.encrypt_last_line(&line),
+            None => line.to_string(),
+        };
+        self.write_line(line).await?;
+        self.file.flush().await?;
+        self.file.sync_all().await?;
+        drop(self.file);
+        tokio::fs::rename(&self.partial_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+/// How to handle secrets from a backup which already exist in the vault being restored to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictStrategy {
+    /// Leave the existing secret as it is.
+    Skip,
+    /// Delete and purge the existing secret, then restore it from the backup. The existing secret is
+    /// backed up first, and put back if the deletion, purge or restore fails. A deleted secret is recovered
+    /// first, so it is put back as an existing secret.
+    /// Requires the secrets/backup, secrets/restore and secrets/delete permissions, plus secrets/recover and
+    /// secrets/purge in vaults with soft-delete enabled.
+    Overwrite,
+    /// Fail the restore, before anything is restored.
+    Fail,
+}
+
+type ProgressCallback<'o> = Box<dyn FnMut(&VaultProgress) + Send + 'o>;
+type SecretFilter<'o> = Box<dyn Fn(&ArchivedSecret) -> bool + Send + 'o>;
+
+/// Options for [`backup_vault`](KeyVaultClient::backup_vault).
+#[derive(Default)]
+pub struct BackupOptions<'o> {
+    progress: Option<ProgressCallback<'o>>,
+    encryption: Option<ArchiveEncryption>,
+}
+
+impl<'o> BackupOptions<'o> {
+    /// Encrypts the archive. The base archive of an incremental backup must be encrypted the same way.
+    pub fn with_encryption(mut self, encryption: ArchiveEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// Sets a callback which is invoked after each secret has been backed up.
+    pub fn with_progress(mut self, progress: impl FnMut(&VaultProgress) + Send + 'o) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+}
+
+/// Options for [`restore_vault`](KeyVaultClient::restore_vault).
+pub struct RestoreOptions<'o> {
+    filter: Option<SecretFilter<'o>>,
+    conflict_strategy: ConflictStrategy,
+    progress: Option<ProgressCallback<'o>>,
+    encryption: Option<ArchiveEncryption>,
+    apply_deletions: bool,
+}
+
+impl<'o> Default for RestoreOptions<'o> {
+    fn default() -> Self {
+        Self {
+            filter: None,
+            conflict_strategy: ConflictStrategy::Fail,
+            progress: None,
+            encryption: None,
+            apply_deletions: false,
+        }
+    }
+}
+
+impl<'o> RestoreOptions<'o> {
+    /// Decrypts the archives, which must have been encrypted with the same passphrase or key file.
+    pub fn with_encryption(mut self, encryption: ArchiveEncryption) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// Only restores the secrets for which the filter returns `true`.
+    pub fn with_filter(mut self, filter: impl Fn(&ArchivedSecret) -> bool + Send + 'o) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Deletes the secrets which were deleted along a chain of archives, if they exist in the vault,
+    /// when restoring with [`restore_vault_chain`](KeyVaultClient::restore_vault_chain). Defaults to `false`.
+    /// Requires the secrets/delete permission.
+    pub fn with_apply_deletions(mut self, apply_deletions: bool) -> Self {
+        self.apply_deletions = apply_deletions;
+        self
+    }
+
+    /// Sets how to handle secrets which already exist in the vault. Defaults to [`ConflictStrategy::Fail`].
+    pub fn with_conflict_strategy(mut self, conflict_strategy: ConflictStrategy) -> Self {
+        self.conflict_strategy = conflict_strategy;
+        self
+    }
+
+    /// Sets a callback which is invoked after each secret has been processed.
+    pub fn with_progress(mut self, progress: impl FnMut(&VaultProgress) + Send + 'o) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+}
+
+fn report_progress(progress: &mut Option<ProgressCallback>, secret_name: &str, completed: usize, total: usize) {
+    if let Some(progress) = progress {
+        progress(&VaultProgress::new(secret_name, completed, total));
+    }
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Backs up every secret in the Key Vault into a single archive file.
+    ///
+    /// The archive holds the backup blob of every secret, along with a manifest of their names, timestamps,
+    /// tags and blob digests. Secrets which fail to back up are reported, and do not fail the whole backup.
+    /// This operation requires the secrets/list and secrets/backup permissions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::backup::BackupOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let options = BackupOptions::default()
+    ///         .with_progress(|p| println!("{}/{} {}", p.completed(), p.total(), p.secret_name()));
+    ///     let report = client.backup_vault("vault.backup", options).await.unwrap();
+    ///     for failure in report.failed() {
+    ///         dbg!(failure);
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn backup_vault(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: BackupOptions<'_>,
+    ) -> Result<VaultOperationReport, KeyVaultError> {
+        self.write_vault_backup(path.as_ref(), None, options).await
+    }
+
+    /// Backs up the secrets which are new or were updated since a previous backup of the Key Vault,
+    /// into an archive which is incremental to the previous one.
+    ///
+    /// `base_path` is the latest archive of the vault, either full or incremental. Secrets whose
+    /// `time_updated` did not change are not backed up again, and are reported as skipped, as are the
+    /// secrets which were deleted from the vault since. A secret which fails to back up is reported, and
+    /// keeps its previous backup.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::backup::BackupOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.backup_vault("vault-full.backup", BackupOptions::default()).await.unwrap();
+    ///     client
+    ///         .backup_vault_incremental("vault-1.backup", "vault-full.backup", BackupOptions::default())
+    ///         .await
+    ///         .unwrap();
+    ///     client
+    ///         .backup_vault_incremental("vault-2.backup", "vault-1.backup", BackupOptions::default())
+    ///         .await
+    ///         .unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn backup_vault_incremental(
+        &mut self,
+        path: impl AsRef<Path>,
+        base_path: impl AsRef<Path>,
+        options: BackupOptions<'_>,
+    ) -> Result<VaultOperationReport, KeyVaultError> {
+        let base = VaultArchive::read(base_path.as_ref(), options.encryption.as_ref()).await?;
+        self.write_vault_backup(path.as_ref(), Some(base), options).await
+    }
+
+    async fn write_vault_backup(
+        &mut self,
+        path: &Path,
+        base: Option<VaultArchive>,
+        mut options: BackupOptions<'_>,
+    ) -> Result<VaultOperationReport, KeyVaultError> {
+        let secrets = self.list_secrets().await?;
+        let base_secrets = base
+            .as_ref()
+            .map(|base| {
+                base.secrets
+                    .iter()
+                    .map(|s| (s.name.as_str(), s))
+                    .collect::<HashMap<_, _>>()
+            })
+            .unwrap_or_default();
+
+        let mut writer = ArchiveWriter::create(path, options.encryption.as_ref()).await?;
+        writer
+            .write(&ArchiveRecord::Header {
+                format: ARCHIVE_FORMAT.to_owned(),
+                version: ARCHIVE_VERSION,
+                vault_url: self.keyvault_endpoint.clone(),
+                time_created: Utc::now(),
+                base_sha256: base.as_ref().map(|base| base.sha256.clone()),
+            })
+            .await?;
+
+        let mut report = VaultOperationReport::default();
+        let mut archived_secrets = Vec::new();
+        for (index, secret) in secrets.iter().enumerate() {
+            let base_secret = base_secrets.get(secret.name().as_str());
+            match base_secret {
+                Some(base_secret) if base_secret.time_updated == *secret.time_updated() => {
+                    archived_secrets.push((*base_secret).clone());
+                    report.record(
+                        secret.name(),
+                        SecretOutcomeStatus::Skipped {
+                            reason: "unchanged since the base backup".to_owned(),
+                        },
+                    );
+                }
+                _ => match self.backup_secret(secret.name()).await {
+                    Ok(blob) => {
+                        writer
+                            .write(&ArchiveRecord::Blob {
+                                secret_name: secret.name().clone(),
+                                value: blob.value().clone(),
+                            })
+                            .await?;
+                        archived_secrets.push(ArchivedSecret {
+                            name: secret.name().clone(),
+                            enabled: *secret.enabled(),
+                            content_type: secret.content_type().clone(),
+                            tags: secret.tags().clone(),
+                            time_created: *secret.time_created(),
+                            time_updated: *secret.time_updated(),
+                            blob_sha256: sha256_hex(blob.value().as_bytes()),
+                        });
+                        report.record(secret.name(), SecretOutcomeStatus::Succeeded);
+                    }
+                    Err(e) => {
+                        // The previous backup is kept, and since its timestamp is outdated the next
+                        // incremental backup tries again.
+                        if let Some(base_secret) = base_secret {
+                            archived_secrets.push((*base_secret).clone());
+                        }
+                        report.record(
+                            secret.name(),
+                            SecretOutcomeStatus::Failed {
+                                error: describe_error(&e),
+                            },
+                        );
+                    }
+                },
+            }
+            report_progress(&mut options.progress, secret.name(), index + 1, secrets.len());
+        }
+
+        let mut deleted_secrets = base_secrets
+            .keys()
+            .filter(|name| !secrets.iter().any(|s| s.name() == *name))
+            .map(|name| (*name).to_owned())
+            .collect::<Vec<_>>();
+        deleted_secrets.sort();
+        for secret_name in &deleted_secrets {
+            report.record(
+                secret_name,
+                SecretOutcomeStatus::Skipped {
+                    reason: "deleted since the base backup".to_owned(),
+                },
+            );
+        }
+
+        writer
+            .finish(&ArchiveRecord::Manifest {
+                secrets: archived_secrets,
+                deleted_secrets,
+            })
+            .await?;
+        Ok(report)
+    }
+
+    /// Restores secrets from an archive created by [`backup_vault`](KeyVaultClient::backup_vault).
+    ///
+    /// The whole archive is verified against the digests in its manifest before anything is restored.
+    /// Backup blobs can only be restored within the same Azure subscription and geography.
+    /// This operation requires the secrets/list and secrets/restore permissions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::backup::{ConflictStrategy, RestoreOptions};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let options = RestoreOptions::default()
+    ///         .with_filter(|s| s.name().starts_with("app-"))
+    ///         .with_conflict_strategy(ConflictStrategy::Skip);
+    ///     let report = client.restore_vault("vault.backup", options).await.unwrap();
+    ///     assert!(report.is_success());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn restore_vault(
+        &mut self,
+        path: impl AsRef<Path>,
+        options: RestoreOptions<'_>,
+    ) -> Result<VaultOperationReport, KeyVaultError> {
+        self.restore_vault_chain(&[path], options).await
+    }
+
+    /// Restores the secrets of a full archive followed by its incremental archives, as of the last one.
+    ///
+    /// `paths` lists the archives in the order they were created, starting with the full archive. Every
+    /// archive is verified, along with the chain itself, before anything is restored. Secrets deleted
+    /// along the chain are not restored, and are reported as skipped; they are also deleted from the vault
+    /// if [`with_apply_deletions`](RestoreOptions::with_apply_deletions) is set. The filter does not apply to them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::backup::RestoreOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let archives = ["vault-full.backup", "vault-1.backup", "vault-2.backup"];
+    ///     let report = client.restore_vault_chain(&archives, RestoreOptions::default()).await.unwrap();
+    ///     assert!(report.is_success());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn restore_vault_chain<P: AsRef<Path>>(
+        &mut self,
+        paths: &[P],
+        mut options: RestoreOptions<'_>,
+    ) -> Result<VaultOperationReport, KeyVaultError> {
+        let chain = VaultArchive::read_chain(paths, options.encryption.as_ref()).await?;
+        let selected = chain
+            .secrets
+            .into_iter()
+            .filter(|(secret, _)| match &options.filter {
+                Some(filter) => filter(secret),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        let existing = self
+            .list_secrets()
+            .await?
+            .into_iter()
+            .map(|s| s.name().clone())
+            .collect::<HashSet<_>>();
+        // A deleted secret keeps its name until it is purged, so restoring over it fails just the same.
+        let deleted = self
+            .list_deleted_secrets()
+            .await?
+            .into_iter()
+            .map(|s| s.name().clone())
+            .collect::<HashSet<_>>();
+        let conflicts = |secret: &ArchivedSecret| existing.contains(&secret.name) || deleted.contains(&secret.name);
+        if options.conflict_strategy == ConflictStrategy::Fail {
+            let conflicts = selected
+                .iter()
+                .filter(|(secret, _)| conflicts(secret))
+                .map(|(secret, _)| secret.name.clone())
+                .collect::<Vec<_>>();
+            if !conflicts.is_empty() {
+                return Err(KeyVaultError::RestoreConflict {
+                    secret_names: conflicts,
+                });
+            }
+        }
+
+        let total = selected.len() + chain.deleted_secrets.len();
+        let mut report = VaultOperationReport::default();
+        for (index, (secret, blob)) in selected.iter().enumerate() {
+            let status = if conflicts(secret) && options.conflict_strategy == ConflictStrategy::Skip {
+                SecretOutcomeStatus::Skipped {
+                    reason: if deleted.contains(&secret.name) {
+                        "a deleted secret with the same name exists".to_owned()
+                    } else {
+                        "the secret already exists".to_owned()
+                    },
+                }
+            } else {
+                let result = if conflicts(secret) {
+                    self.overwrite_secret(&secret.name, blob, deleted.contains(&secret.name))
+                        .await
+                } else {
+                    self.restore_secret(blob).await
+                };
+                result.map_or_else(
+                    |e| SecretOutcomeStatus::Failed {
+                        error: describe_error(&e),
+                    },
+                    |_| SecretOutcomeStatus::Succeeded,
+                )
+            };
+            report.record(&secret.name, status);
+            report_progress(&mut options.progress, &secret.name, index + 1, total);
+        }
+
+        for (index, secret_name) in chain.deleted_secrets.iter().enumerate() {
+            let status = if !existing.contains(secret_name) {
+                SecretOutcomeStatus::Skipped {
+                    reason: "deleted since the base backup".to_owned(),
+                }
+            } else if options.apply_deletions {
+                let deleted = match self.delete_secret(secret_name).await {
+                    Ok(deletion) => deletion.wait().await,
+                    Err(e) => Err(e),
+                };
+                deleted.map_or_else(
+                    |e| SecretOutcomeStatus::Failed {
+                        error: describe_error(&e),
+                    },
+                    |_| SecretOutcomeStatus::Succeeded,
+                )
+            } else {
+                SecretOutcomeStatus::Skipped {
+                    reason: "deleted since the base backup, but kept in the vault".to_owned(),
+                }
+            };
+            report.record(secret_name, status);
+            report_progress(&mut options.progress, secret_name, selected.len() + index + 1, total);
+        }
+        Ok(report)
+    }
+
+    /// Replaces a secret with a backup blob. The secret is backed up first, and put back if any step after
+    /// its deletion fails, so a failed overwrite never loses it.
+    async fn overwrite_secret(&mut self, secret_name: &str, blob: &str, deleted: bool) -> Result<(), KeyVaultError> {
+        if deleted {
+            self.recover_deleted_secret(secret_name).await?.wait().await?;
+        }
+        let previous = self.backup_secret(secret_name).await?;
+        let deletion = self.delete_secret(secret_name).await?;
+        let soft_delete = deletion.soft_delete();
+
+        let mut result = deletion.wait().await;
+        // Until it is purged, the deleted secret can be recovered as it was.
+        let mut recoverable = soft_delete;
+        if result.is_ok() && soft_delete {
+            result = match self.purge_deleted_secret(secret_name).await {
+                Ok(purge) => purge.wait().await,
+                Err(e) => Err(e),
+            };
+            recoverable = result.is_err();
+        }
+        if result.is_ok() {
+            result = self.restore_secret(blob).await;
+        }
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => match self.put_back_secret(secret_name, previous.value(), recoverable).await {
+                Ok(()) => Err(e),
+                Err(rollback_error) => Err(KeyVaultError::GeneralError(format!(
+                    "{}, and putting back the previous secret failed: {}",
+                    describe_error(&e),
+                    describe_error(&rollback_error)
+                ))),
+            },
+        }
+    }
+
+    /// Puts back a secret deleted by an overwrite: recovered if it may still be deleted, restored from its backup
+    /// if that fails, or if it was purged or deleted without soft-delete.
+    async fn put_back_secret(
+        &mut self,
+        secret_name: &str,
+        previous: &str,
+        recoverable: bool,
+    ) -> Result<(), KeyVaultError> {
+        if recoverable {
+            let recovered = match self.recover_deleted_secret(secret_name).await {
+                Ok(recovery) => recovery.wait().await,
+                Err(e) => Err(e),
+            };
+            if recovered.is_ok() {
+                return Ok(());
+            }
+        }
+        self.restore_secret(previous).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::report::SecretOutcome;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    fn mock_list_secrets(secret_names: &[&str]) -> mockito::Mock {
+        mock_list_secrets_at("", secret_names)
+    }
+
+    fn mock_list_secrets_at(prefix: &str, secret_names: &[&str]) -> mockito::Mock {
+        let secrets = secret_names
+            .iter()
+            .map(|name| {
+                json!({
+                    "id": format!("https://test-keyvault.vault.azure.net/secrets/{}", name),
+                    "tags": { "owner": "team-a" },
+                    "attributes": {
+                        "enabled": true,
+                        "created": 1_600_000_000,
+                        "updated": 1_600_000_000,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        mock("GET", format!("{}/secrets", prefix).as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "25".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "value": secrets, "nextLink": null }).to_string())
+            .with_status(200)
+            .create()
+    }
+
+    fn mock_list_deleted_secrets_at(prefix: &str, secret_names: &[&str]) -> mockito::Mock {
+        let secrets = secret_names
+            .iter()
+            .map(|name| {
+                json!({
+                    "id": format!("https://test-keyvault.vault.azure.net/secrets/{}", name),
+                    "recoveryId": format!("https://test-keyvault.vault.azure.net/deletedsecrets/{}", name),
+                    "deletedDate": 1_600_000_000,
+                    "scheduledPurgeDate": 1_600_000_000,
+                    "attributes": {
+                        "enabled": true,
+                        "created": 1_600_000_000,
+                        "updated": 1_600_000_000,
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        mock("GET", format!("{}/deletedsecrets", prefix).as_str())
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "25".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "value": secrets, "nextLink": null }).to_string())
+            .with_status(200)
+            .create()
+    }
+
+    fn archive_path(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "azure-sdk-keyvault-test-{}-{}.backup",
+            std::process::id(),
+            label
+        ))
+    }
+
+    fn secret_names<'r>(outcomes: impl Iterator<Item = &'r SecretOutcome>) -> Vec<&'r str> {
+        outcomes.map(|o| o.secret_name().as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn backup_and_restore_vault() {
+        let path = std::env::temp_dir().join(format!("azure-sdk-keyvault-test-{}.backup", std::process::id()));
+
+        let list = mock_list_secrets(&["backed-up-secret", "forbidden-secret"]);
+        let _m1 = mock("POST", "/secrets/backed-up-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "BACKUP_BLOB" }).to_string())
+            .with_status(200)
+            .create();
+        let _m2 = mock("POST", "/secrets/forbidden-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "error": { "code": "Forbidden", "message": "Access denied" } }).to_string())
+            .with_status(403)
+            .create();
+        let _deleted_list = mock_list_deleted_secrets_at("", &[]);
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let mut progress_calls = 0;
+        let report = client
+            .backup_vault(&path, BackupOptions::default().with_progress(|_| progress_calls += 1))
+            .await
+            .unwrap();
+        assert_eq!(2, progress_calls);
+        assert_eq!(
+            vec!["backed-up-secret"],
+            report.succeeded().map(|o| o.secret_name().as_str()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["forbidden-secret"],
+            report.failed().map(|o| o.secret_name().as_str()).collect::<Vec<_>>()
+        );
+
+        drop(list);
+        let _list = mock_list_secrets(&[]);
+        let restore = mock("POST", "/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": "BACKUP_BLOB" })))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let report = client
+            .restore_vault(
+                &path,
+                RestoreOptions::default().with_filter(|s| s.tags().get("owner").map(String::as_str) == Some("team-a")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(1, report.succeeded().count());
+        restore.assert();
+
+        // Tampering with a backup blob fails the restore before anything is sent to the vault.
+        let tampered = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("BACKUP_BLOB", "TAMPERED_BLOB");
+        std::fs::write(&path, tampered).unwrap();
+        let err = client
+            .restore_vault(&path, RestoreOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidArchive(_)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn incremental_backup_and_chained_restore() {
+        let prefix = "/incremental";
+        let paths = [
+            archive_path("incremental-0"),
+            archive_path("incremental-1"),
+            archive_path("incremental-2"),
+        ];
+
+        let list = mock_list_secrets_at(prefix, &["unchanged-secret"]);
+        let _deleted_list = mock_list_deleted_secrets_at(prefix, &[]);
+        let _m1 = mock("POST", "/incremental/secrets/unchanged-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "UNCHANGED_BLOB" }).to_string())
+            .expect(1)
+            .with_status(200)
+            .create();
+        let _m2 = mock("POST", "/incremental/secrets/new-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "NEW_BLOB" }).to_string())
+            .expect(1)
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}{}", mockito::server_url(), prefix);
+
+        client.backup_vault(&paths[0], BackupOptions::default()).await.unwrap();
+
+        // Incremental backups only hold the secrets which changed.
+        drop(list);
+        let list = mock_list_secrets_at(prefix, &["unchanged-secret", "new-secret"]);
+        let report = client
+            .backup_vault_incremental(&paths[1], &paths[0], BackupOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(vec!["new-secret"], secret_names(report.succeeded()));
+        assert_eq!(vec!["unchanged-secret"], secret_names(report.skipped()));
+
+        drop(list);
+        let list = mock_list_secrets_at(prefix, &["new-secret"]);
+        let report = client
+            .backup_vault_incremental(&paths[2], &paths[1], BackupOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(vec!["new-secret", "unchanged-secret"], secret_names(report.skipped()));
+
+        // The chain is restored as of its last archive, so the deleted secret is not restored.
+        drop(list);
+        let _empty_list = mock_list_secrets_at(prefix, &[]);
+        let restore = mock("POST", "/incremental/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": "NEW_BLOB" })))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let report = client
+            .restore_vault_chain(&paths, RestoreOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(vec!["new-secret"], secret_names(report.succeeded()));
+        assert_eq!(vec!["unchanged-secret"], secret_names(report.skipped()));
+        restore.assert();
+
+        // Deletions along the chain are applied to the vault on request.
+        let _list = mock_list_secrets_at(prefix, &["unchanged-secret", "new-secret"]);
+        let delete = mock("DELETE", "/incremental/secrets/unchanged-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                json!({ "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/unchanged-secret" })
+                    .to_string(),
+            )
+            .with_status(200)
+            .expect(1)
+            .create();
+        let _deleted = mock("GET", "/incremental/deletedsecrets/unchanged-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                json!({ "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/unchanged-secret" })
+                    .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let report = client
+            .restore_vault_chain(
+                &paths,
+                RestoreOptions::default()
+                    .with_conflict_strategy(ConflictStrategy::Skip)
+                    .with_apply_deletions(true),
+            )
+            .await
+            .unwrap();
+        assert_eq!(vec!["unchanged-secret"], secret_names(report.succeeded()));
+        assert_eq!(vec!["new-secret"], secret_names(report.skipped()));
+        delete.assert();
+
+        for broken_chain in &[vec![&paths[2]], vec![&paths[0], &paths[2]]] {
+            let err = client
+                .restore_vault_chain(broken_chain, RestoreOptions::default())
+                .await
+                .unwrap_err();
+            assert!(matches!(err, KeyVaultError::InvalidArchive(_)));
+        }
+
+        for path in &paths {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn overwrite_puts_back_the_previous_secret_when_the_restore_fails() {
+        let path = archive_path("overwrite");
+        let prefix = "/overwrite";
+
+        let list = mock_list_secrets_at(prefix, &["overwritten-secret"]);
+        let backup = mock("POST", "/overwrite/secrets/overwritten-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "NEW_BLOB" }).to_string())
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}{}", mockito::server_url(), prefix);
+
+        client.backup_vault(&path, BackupOptions::default()).await.unwrap();
+        drop(backup);
+        drop(list);
+
+        // A deleted secret with the same name conflicts just like an existing one.
+        let _empty_list = mock_list_secrets_at(prefix, &[]);
+        let deleted_list = mock_list_deleted_secrets_at(prefix, &["overwritten-secret"]);
+        let err = client
+            .restore_vault(&path, RestoreOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::RestoreConflict { .. }));
+        drop(deleted_list);
+
+        let _list = mock_list_secrets_at(prefix, &["overwritten-secret"]);
+        let _deleted_list = mock_list_deleted_secrets_at(prefix, &[]);
+        let _m1 = mock("POST", "/overwrite/secrets/overwritten-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "PREVIOUS_BLOB" }).to_string())
+            .with_status(200)
+            .create();
+        let _m2 = mock("DELETE", "/overwrite/secrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                json!({
+                    "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/overwritten-secret"
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+        // Found once deleted, then gone once purged.
+        let polls = std::sync::atomic::AtomicUsize::new(0);
+        let _m3 = mock("GET", "/overwrite/deletedsecrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body_from_fn(move |w| {
+                let body = match polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+                    0 => json!({ "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/overwritten-secret" }),
+                    _ => json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }),
+                };
+                w.write_all(body.to_string().as_bytes())
+            })
+            .with_status(200)
+            .create();
+        let _m4 = mock("DELETE", "/overwrite/deletedsecrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_status(204)
+            .create();
+        let _m5 = mock("POST", "/overwrite/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": "NEW_BLOB" })))
+            .with_body(
+                json!({ "error": { "code": "Forbidden", "message": "Backup belongs to another subscription" } })
+                    .to_string(),
+            )
+            .with_status(403)
+            .create();
+        let put_back = mock("POST", "/overwrite/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": "PREVIOUS_BLOB" })))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let report = client
+            .restore_vault(
+                &path,
+                RestoreOptions::default().with_conflict_strategy(ConflictStrategy::Overwrite),
+            )
+            .await
+            .unwrap();
+        let failure = report.failed().next().unwrap();
+        assert!(
+            matches!(failure.status(), SecretOutcomeStatus::Failed { error } if error.contains("another subscription"))
+        );
+        put_back.assert();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn overwrite_recovers_the_previous_secret_when_the_purge_fails() {
+        let path = archive_path("overwrite-purge");
+        let prefix = "/overwrite-purge";
+
+        let list = mock_list_secrets_at(prefix, &["overwritten-secret"]);
+        let _deleted_list = mock_list_deleted_secrets_at(prefix, &[]);
+        let backup = mock("POST", "/overwrite-purge/secrets/overwritten-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "NEW_BLOB" }).to_string())
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}{}", mockito::server_url(), prefix);
+
+        client.backup_vault(&path, BackupOptions::default()).await.unwrap();
+        drop(backup);
+        drop(list);
+
+        let _list = mock_list_secrets_at(prefix, &["overwritten-secret"]);
+        let _m1 = mock("POST", "/overwrite-purge/secrets/overwritten-secret/backup")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": "PREVIOUS_BLOB" }).to_string())
+            .with_status(200)
+            .create();
+        let _m2 = mock("DELETE", "/overwrite-purge/secrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                json!({
+                    "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/overwritten-secret"
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m3 = mock("GET", "/overwrite-purge/deletedsecrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                json!({ "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/overwritten-secret" })
+                    .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m4 = mock("DELETE", "/overwrite-purge/deletedsecrets/overwritten-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "error": { "code": "Forbidden", "message": "Purge not allowed" } }).to_string())
+            .with_status(403)
+            .create();
+        let recover = mock("POST", "/overwrite-purge/deletedsecrets/overwritten-secret/recover")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let _m5 = mock("GET", "/overwrite-purge/secrets/overwritten-secret/versions")
+            .match_query(Matcher::Regex(format!("api-version={}", API_VERSION)))
+            .with_body(
+                json!({
+                    "value": [{
+                        "id": "https://test-keyvault.vault.azure.net/secrets/overwritten-secret/VERSION",
+                        "attributes": { "enabled": true, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                    }],
+                    "nextLink": null
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let restore = mock("POST", "/overwrite-purge/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .expect(0)
+            .create();
+
+        let report = client
+            .restore_vault(
+                &path,
+                RestoreOptions::default().with_conflict_strategy(ConflictStrategy::Overwrite),
+            )
+            .await
+            .unwrap();
+        let failure = report.failed().next().unwrap();
+        assert!(
+            matches!(failure.status(), SecretOutcomeStatus::Failed { error } if error.contains("Purge not allowed"))
+        );
+        recover.assert();
+        restore.assert();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,539 @@
+use crate::report::{describe_error, SecretOutcomeStatus, VaultOperationReport};
+use crate::secret::KeyVaultSecret;
+use crate::{KeyVaultClient, KeyVaultError};
+use getset::CopyGetters;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
+
+const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_REFRESH_AHEAD: Duration = Duration::from_secs(60);
+const DEFAULT_MAX_STALE: Duration = Duration::from_secs(60 * 60);
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(30);
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Options for a [`SecretCache`].
+#[derive(Debug, Clone)]
+pub struct CacheOptions {
+    ttl: Duration,
+    secret_ttls: HashMap<String, Duration>,
+    refresh_ahead: Duration,
+    max_stale: Duration,
+    negative_ttl: Duration,
+    idle_timeout: Duration,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            ttl: DEFAULT_CACHE_TTL,
+            secret_ttls: HashMap::new(),
+            refresh_ahead: DEFAULT_REFRESH_AHEAD,
+            max_stale: DEFAULT_MAX_STALE,
+            negative_ttl: DEFAULT_NEGATIVE_TTL,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+        }
+    }
+}
+
+impl CacheOptions {
+    /// How long secrets are served from the cache. Defaults to 5 minutes.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long a given secret is served from the cache, instead of the default TTL.
+    pub fn with_secret_ttl(mut self, secret_name: impl Into<String>, ttl: Duration) -> Self {
+        self.secret_ttls.insert(secret_name.into(), ttl);
+        self
+    }
+
+    /// How long before expiring a secret is refreshed by [`refresh_due`](SecretCache::refresh_due),
+    /// and so by [`spawn_refresher`](SharedSecretCache::spawn_refresher). Defaults to 1 minute.
+    pub fn with_refresh_ahead(mut self, refresh_ahead: Duration) -> Self {
+        self.refresh_ahead = refresh_ahead;
+        self
+    }
+
+    /// How long past its expiry a secret is still served if it can not be refreshed. Defaults to 1 hour.
+    pub fn with_max_stale(mut self, max_stale: Duration) -> Self {
+        self.max_stale = max_stale;
+        self
+    }
+
+    /// How long a secret which does not exist is remembered as missing. Defaults to 30 seconds.
+    pub fn with_negative_ttl(mut self, negative_ttl: Duration) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
+    /// How long a secret stays cached without being requested.
+    /// Idle secrets are not refreshed, but evicted by [`refresh_due`](SecretCache::refresh_due). Defaults to 30 minutes.
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    fn ttl(&self, secret_name: &str, secret: &Option<KeyVaultSecret>) -> Duration {
+        match secret {
+            Some(_) => *self.secret_ttls.get(secret_name).unwrap_or(&self.ttl),
+            None => self.negative_ttl,
+        }
+    }
+}
+
+/// Counters of how requests to a [`SecretCache`] were served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, CopyGetters)]
+#[getset(get_copy = "pub")]
+pub struct CacheStats {
+    /// Requests served from the cache, including for missing secrets.
+    hits: u64,
+    /// Requests which had to get the secret from the Key Vault.
+    misses: u64,
+    /// Requests served from the cache past expiry, because the secret could not be refreshed.
+    stale_hits: u64,
+    /// Secrets refreshed by [`refresh_due`](SecretCache::refresh_due).
+    refreshes: u64,
+    /// Secrets which [`refresh_due`](SecretCache::refresh_due) failed to refresh.
+    refresh_failures: u64,
+    /// Secrets which [`refresh_due`](SecretCache::refresh_due) evicted, because they were not requested recently.
+    evictions: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    /// `None` when the secret does not exist.
+    secret: Option<KeyVaultSecret>,
+    time_fetched: Instant,
+    /// When the secret was last requested, which refreshes do not count as.
+    time_accessed: Instant,
+    ttl: Duration,
+}
+
+/// An in-memory cache of secrets in front of a [`KeyVaultClient`].
+///
+/// Secrets are served from the cache until their TTL elapses, after which they are fetched again.
+/// If that fails, the expired secret keeps being served for up to the maximum staleness.
+/// To keep hot secrets from ever expiring, call [`refresh_due`](SecretCache::refresh_due) periodically,
+/// which refreshes the secrets about to expire and evicts the idle ones, or have it called in the background by
+/// [`spawn_refresher`](SharedSecretCache::spawn_refresher).
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::cache::{CacheOptions, SecretCache};
+/// use std::time::Duration;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let client = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"KEYVAULT_NAME",
+///     );
+///     let options = CacheOptions::default().with_secret_ttl("feature-flags", Duration::from_secs(30));
+///     let mut cache = SecretCache::new(client, options);
+///     cache.warm_up(&["db-password", "feature-flags"]).await;
+///
+///     let secret = cache.get_secret(&"db-password").await.unwrap();
+///     dbg!(secret.is_some(), cache.stats());
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+#[derive(Debug)]
+pub struct SecretCache<'a> {
+    client: KeyVaultClient<'a>,
+    options: CacheOptions,
+    entries: HashMap<(String, String), CacheEntry>,
+    stats: CacheStats,
+}
+
+impl<'a> SecretCache<'a> {
+    pub fn new(client: KeyVaultClient<'a>, options: CacheOptions) -> Self {
+        Self {
+            client,
+            options,
+            entries: HashMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// The client behind the cache, for example to set secrets.
+    /// Secrets changed through it are not seen until they are refreshed or [invalidated](SecretCache::invalidate).
+    pub fn client(&mut self) -> &mut KeyVaultClient<'a> {
+        &mut self.client
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Gets the latest version of a secret, or `None` if it does not exist.
+    pub async fn get_secret(&mut self, secret_name: &str) -> Result<Option<KeyVaultSecret>, KeyVaultError> {
+        self.get_secret_with_version(secret_name, "").await
+    }
+
+    /// Gets a version of a secret, or `None` if it does not exist.
+    pub async fn get_secret_with_version(
+        &mut self,
+        secret_name: &str,
+        secret_version_name: &str,
+    ) -> Result<Option<KeyVaultSecret>, KeyVaultError> {
+        let key = (secret_name.to_owned(), secret_version_name.to_owned());
+        let age = match self.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.time_accessed = Instant::now();
+                let age = entry.time_fetched.elapsed();
+                if age < entry.ttl {
+                    self.stats.hits += 1;
+                    return Ok(entry.secret.clone());
+                }
+                Some(age)
+            }
+            None => None,
+        };
+
+        self.stats.misses += 1;
+        match self.fetch(&key).await {
+            Ok(secret) => Ok(secret),
+            Err(e) => {
+                let entry = self.entries.get(&key);
+                match (entry, age) {
+                    (Some(entry), Some(age)) if entry.secret.is_some() && age < entry.ttl + self.options.max_stale => {
+                        self.stats.stale_hits += 1;
+                        Ok(entry.secret.clone())
+                    }
+                    _ => Err(e),
+                }
+            }
+        }
+    }
+
+    /// Refreshes every cached secret which expires within the refresh-ahead window, or has already expired,
+    /// and evicts the secrets which have not been requested within the idle timeout.
+    /// Meant to be called periodically, so that requests keep being served from the cache.
+    /// Only the latest versions of existing secrets are refreshed: pinned versions do not change,
+    /// and missing secrets are looked up again when next requested.
+    /// Secrets which fail to refresh are kept, and served stale once expired.
+    pub async fn refresh_due(&mut self) -> VaultOperationReport {
+        let mut report = VaultOperationReport::default();
+        for key in self.take_due() {
+            let result = self.client.find_secret(&key.0, &key.1).await;
+            let status = self.store_refreshed(&key, result);
+            report.record(&key.0, status);
+        }
+        report
+    }
+
+    /// Loads the latest version of each secret into the cache, typically at startup.
+    /// Secrets which fail to load are reported, and fetched again when first requested.
+    pub async fn warm_up(&mut self, secret_names: &[&str]) -> VaultOperationReport {
+        let mut report = VaultOperationReport::default();
+        for secret_name in secret_names {
+            let status = match self.fetch(&((*secret_name).to_owned(), String::new())).await {
+                Ok(Some(_)) => SecretOutcomeStatus::Succeeded,
+                Ok(None) => SecretOutcomeStatus::Skipped {
+                    reason: "not found".to_owned(),
+                },
+                Err(e) => SecretOutcomeStatus::Failed {
+                    error: describe_error(&e),
+                },
+            };
+            report.record(secret_name, status);
+        }
+        report
+    }
+
+    /// Removes every cached version of a secret, so that it is fetched again when next requested.
+    pub fn invalidate(&mut self, secret_name: &str) {
+        self.entries.retain(|(name, _), _| name != secret_name);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    async fn fetch(&mut self, key: &(String, String)) -> Result<Option<KeyVaultSecret>, KeyVaultError> {
+        let secret = self.client.find_secret(&key.0, &key.1).await?;
+        let now = Instant::now();
+        self.entries.insert(
+            key.clone(),
+            CacheEntry {
+                ttl: self.options.ttl(&key.0, &secret),
+                secret: secret.clone(),
+                time_fetched: now,
+                time_accessed: now,
+            },
+        );
+        Ok(secret)
+    }
+
+    /// Evicts the idle secrets, and returns those to refresh.
+    fn take_due(&mut self) -> Vec<(String, String)> {
+        let idle_timeout = self.options.idle_timeout;
+        let count = self.entries.len();
+        self.entries
+            .retain(|_, entry| entry.time_accessed.elapsed() < idle_timeout);
+        self.stats.evictions += (count - self.entries.len()) as u64;
+
+        let refresh_ahead = self.options.refresh_ahead;
+        self.entries
+            .iter()
+            .filter(|((_, version), entry)| {
+                version.is_empty()
+                    && entry.secret.is_some()
+                    && entry.time_fetched.elapsed() + refresh_ahead >= entry.ttl
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Stores the outcome of refreshing a secret, unless it was invalidated or evicted in the meantime.
+    fn store_refreshed(
+        &mut self,
+        key: &(String, String),
+        result: Result<Option<KeyVaultSecret>, KeyVaultError>,
+    ) -> SecretOutcomeStatus {
+        match result {
+            Ok(secret) => {
+                self.stats.refreshes += 1;
+                if let Some(entry) = self.entries.get_mut(key) {
+                    entry.ttl = self.options.ttl(&key.0, &secret);
+                    entry.secret = secret;
+                    entry.time_fetched = Instant::now();
+                }
+                SecretOutcomeStatus::Succeeded
+            }
+            Err(e) => {
+                self.stats.refresh_failures += 1;
+                SecretOutcomeStatus::Failed {
+                    error: describe_error(&e),
+                }
+            }
+        }
+    }
+}
+
+impl SecretCache<'static> {
+    /// Moves the cache behind a handle which can be cloned and shared between tasks,
+    /// so that it can be refreshed in the background.
+    pub fn into_shared(self) -> SharedSecretCache {
+        SharedSecretCache(Arc::new(Mutex::new(self)))
+    }
+}
+
+/// A [`SecretCache`] shared between tasks, created by [`into_shared`](SecretCache::into_shared).
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::cache::{CacheOptions, SecretCache};
+/// use std::time::Duration;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let client = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"KEYVAULT_NAME",
+///     );
+///     let cache = SecretCache::new(client, CacheOptions::default()).into_shared();
+///     cache.spawn_refresher(Duration::from_secs(30));
+///
+///     let secret = cache.lock().await.get_secret(&"db-password").await.unwrap();
+///     dbg!(secret.is_some());
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedSecretCache(Arc<Mutex<SecretCache<'static>>>);
+
+impl SharedSecretCache {
+    /// Waits for exclusive access to the cache.
+    pub async fn lock(&self) -> MutexGuard<'_, SecretCache<'static>> {
+        self.0.lock().await
+    }
+
+    /// Spawns a task which does the work of [`refresh_due`](SecretCache::refresh_due) every `interval`.
+    /// The cache is only locked to pick the secrets to refresh and to store them,
+    /// so requests are not held up while secrets are fetched.
+    /// The task stops once every handle to the cache has been dropped.
+    pub fn spawn_refresher(&self, interval: Duration) -> JoinHandle<()> {
+        let cache: Weak<Mutex<SecretCache<'static>>> = Arc::downgrade(&self.0);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::delay_for(interval).await;
+                let cache = match cache.upgrade() {
+                    Some(cache) => cache,
+                    None => return,
+                };
+                let (mut client, due) = {
+                    let mut cache = cache.lock().await;
+                    (cache.client.clone(), cache.take_due())
+                };
+                for key in due {
+                    let result = client.find_secret(&key.0, &key.1).await;
+                    cache.lock().await.store_refreshed(&key, result);
+                }
+                // Keep the access token the refresh may have renewed.
+                let mut cache = cache.lock().await;
+                if client.token_expiration > cache.client.token_expiration {
+                    cache.client.token = client.token;
+                    cache.client.token_expiration = client.token_expiration;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{mock_get, mock_get_expecting, secret_bundle};
+
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn cache_secrets() {
+        let cached = mock_get_expecting(
+            "/cache/secrets/cached-secret/",
+            secret_bundle("cached-secret", "VERSION_1", "secret-value"),
+            2,
+        );
+        let missing = mock_get_expecting(
+            "/cache/secrets/missing-secret/",
+            json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }),
+            1,
+        );
+        let stale = mock_get_expecting(
+            "/cache/secrets/stale-secret/",
+            secret_bundle("stale-secret", "VERSION_1", "secret-value"),
+            1,
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/cache", mockito::server_url());
+        let options = CacheOptions::default()
+            .with_refresh_ahead(Duration::from_secs(0))
+            .with_secret_ttl("stale-secret", Duration::from_secs(0));
+        let mut cache = SecretCache::new(client, options);
+
+        assert!(cache.warm_up(&["cached-secret"]).await.is_success());
+        for _ in 0..2 {
+            let secret = cache.get_secret("cached-secret").await.unwrap().unwrap();
+            assert_eq!("secret-value", secret.value().expose());
+            assert!(cache.get_secret("missing-secret").await.unwrap().is_none());
+        }
+
+        // Only the expired secret is due.
+        assert!(cache.get_secret("stale-secret").await.unwrap().is_some());
+        drop(stale);
+        let report = cache.refresh_due().await;
+        assert_eq!(1, report.failed().count());
+        assert!(cache.get_secret("stale-secret").await.unwrap().is_some());
+
+        cache.invalidate("cached-secret");
+        cache.get_secret("cached-secret").await.unwrap();
+        cached.assert();
+        missing.assert();
+
+        let stats = cache.stats();
+        assert_eq!(3, stats.hits());
+        assert_eq!(4, stats.misses());
+        assert_eq!(1, stats.stale_hits());
+    }
+
+    #[tokio::test]
+    async fn refresh_only_recently_requested_secrets() {
+        let hot = mock_get_expecting(
+            "/idle/secrets/hot-secret/",
+            secret_bundle("hot-secret", "VERSION_1", "secret-value"),
+            2,
+        );
+        let pinned = mock_get_expecting(
+            "/idle/secrets/hot-secret/VERSION_1",
+            secret_bundle("hot-secret", "VERSION_1", "secret-value"),
+            1,
+        );
+        let missing = mock_get_expecting(
+            "/idle/secrets/missing-secret/",
+            json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }),
+            1,
+        );
+        let idle = mock_get_expecting(
+            "/idle/secrets/idle-secret/",
+            secret_bundle("idle-secret", "VERSION_1", "secret-value"),
+            1,
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/idle", mockito::server_url());
+        // Every secret is due once the refresh-ahead window covers the whole TTL.
+        let options = CacheOptions::default()
+            .with_refresh_ahead(DEFAULT_CACHE_TTL)
+            .with_idle_timeout(Duration::from_secs(60));
+        let mut cache = SecretCache::new(client, options);
+        assert!(cache.warm_up(&["idle-secret"]).await.is_success());
+        let idle_key = ("idle-secret".to_owned(), String::new());
+        cache.entries.get_mut(&idle_key).unwrap().time_accessed -= Duration::from_secs(120);
+
+        assert!(cache.get_secret("hot-secret").await.unwrap().is_some());
+        assert!(cache
+            .get_secret_with_version("hot-secret", "VERSION_1")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(cache.get_secret("missing-secret").await.unwrap().is_none());
+        let report = cache.refresh_due().await;
+        assert_eq!(
+            vec!["hot-secret"],
+            report
+                .outcomes()
+                .iter()
+                .map(|o| o.secret_name().as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(report.is_success());
+
+        let stats = cache.stats();
+        assert_eq!(1, stats.refreshes());
+        assert_eq!(1, stats.evictions());
+        hot.assert();
+        pinned.assert();
+        missing.assert();
+        idle.assert();
+    }
+
+    #[tokio::test]
+    async fn refresh_in_the_background() {
+        let _m = mock_get(
+            "/refresher/secrets/refreshed-secret/",
+            secret_bundle("refreshed-secret", "VERSION_1", "secret-value"),
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/refresher", mockito::server_url());
+        // Every secret is due once the refresh-ahead window covers the whole TTL.
+        let options = CacheOptions::default().with_refresh_ahead(DEFAULT_CACHE_TTL);
+        let cache = SecretCache::new(client, options).into_shared();
+        assert!(cache.lock().await.warm_up(&["refreshed-secret"]).await.is_success());
+
+        let refresher = cache.spawn_refresher(Duration::from_millis(10));
+        tokio::time::delay_for(Duration::from_millis(200)).await;
+        let stats = cache.lock().await.stats();
+        assert!(stats.refreshes() >= 2);
+        assert_eq!(0, stats.refresh_failures());
+
+        drop(cache);
+        refresher.await.unwrap();
+    }
+}
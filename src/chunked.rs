@@ -0,0 +1,306 @@
+use crate::validation::{validate_secret_name, MAX_SECRET_VALUE_SIZE};
+use crate::{sha256_hex, KeyVaultClient, KeyVaultError, SecretValue};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+pub(crate) const CHUNKED_MANIFEST_CONTENT_TYPE: &str = "application/vnd.azure-keyvault-chunked-manifest+json";
+pub(crate) const CHUNK_CONTENT_TYPE: &str = "application/vnd.azure-keyvault-chunk";
+
+/// Key Vault rejects secret values over 25 KB, so chunks leave some headroom below that.
+pub(crate) const MAX_CHUNK_SIZE: usize = 24 * 1024;
+
+/// Stored as the value of the secret itself, pointing at the exact chunk versions it was written with.
+#[derive(Serialize, Deserialize, Debug)]
+struct ChunkedSecretManifest {
+    length: usize,
+    sha256: String,
+    chunk_versions: Vec<String>,
+}
+
+fn chunk_name(secret_name: &str, index: usize) -> String {
+    format!("{}--{}", secret_name, index)
+}
+
+/// Splits a value into chunks of at most `max_chunk_size` bytes, without splitting any character.
+fn split_chunks(value: &str, max_chunk_size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = value;
+    while !rest.is_empty() {
+        let mut end = max_chunk_size.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, remainder) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remainder;
+    }
+    chunks
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Sets a value which may exceed the 25 KB secret size limit, by splitting it across the secrets
+    /// `{secret_name}--0`, `{secret_name}--1`, etc.
+    ///
+    /// The secret `{secret_name}` itself holds a manifest with the length and SHA-256 digest of the value,
+    /// and the exact version of every chunk. The manifest is only written once all chunks have been,
+    /// so readers never observe a partially written value - an interrupted write leaves the previous
+    /// manifest, and the chunk versions it points at, untouched.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let config = std::fs::read_to_string("config.json").unwrap();
+    ///     client.set_chunked_secret(&"SECRET_NAME", &config).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_chunked_secret(&mut self, secret_name: &str, value: &str) -> Result<(), KeyVaultError> {
+        let chunks = split_chunks(value, MAX_CHUNK_SIZE);
+        // Checked up front, so that a name too long for the last chunk never leaves the first ones behind.
+        validate_secret_name(&chunk_name(secret_name, chunks.len().saturating_sub(1)))?;
+        let mut chunk_versions = Vec::new();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let chunk_secret = self
+                .set_secret_version(&chunk_name(secret_name, index), chunk, Some(CHUNK_CONTENT_TYPE))
+                .await?;
+            chunk_versions.push(chunk_secret.version().clone());
+        }
+
+        let manifest = ChunkedSecretManifest {
+            length: value.len(),
+            sha256: sha256_hex(value.as_bytes()),
+            chunk_versions,
+        };
+        self.set_secret_with_content_type(
+            secret_name,
+            &serde_json::to_string(&manifest).unwrap(),
+            Some(CHUNKED_MANIFEST_CONTENT_TYPE),
+        )
+        .await
+    }
+
+    /// Gets a value written with [`set_chunked_secret`](KeyVaultClient::set_chunked_secret), reassembling
+    /// its chunks and verifying them against the length and digest recorded in the manifest.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let config = client.get_chunked_secret(&"SECRET_NAME").await.unwrap();
+    ///     std::fs::write("config.json", config.expose()).unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn get_chunked_secret(&mut self, secret_name: &str) -> Result<SecretValue, KeyVaultError> {
+        let manifest_secret = self.get_secret(secret_name).await?;
+        if manifest_secret.content_type().as_deref() != Some(CHUNKED_MANIFEST_CONTENT_TYPE) {
+            return Err(KeyVaultError::ContentTypeMismatch {
+                secret_name: secret_name.to_owned(),
+                expected: CHUNKED_MANIFEST_CONTENT_TYPE.to_owned(),
+                actual: manifest_secret.content_type().clone(),
+            });
+        }
+        let corrupted = |reason: String| KeyVaultError::ChunkedSecretCorrupted {
+            secret_name: secret_name.to_owned(),
+            reason,
+        };
+        let manifest = serde_json::from_str::<ChunkedSecretManifest>(manifest_secret.value().expose())
+            .map_err(|e| corrupted(format!("the manifest could not be parsed: {}", e)))?;
+
+        // The length comes from the vault, so it is bounded by what the chunks can hold before allocating.
+        let max_length = manifest.chunk_versions.len().saturating_mul(MAX_SECRET_VALUE_SIZE);
+        if manifest.length > max_length {
+            return Err(corrupted(format!(
+                "the manifest records {} bytes, more than its {} chunks can hold",
+                manifest.length,
+                manifest.chunk_versions.len()
+            )));
+        }
+        // Allocated upfront, so that the buffer is never reallocated and leaves no copies of the value behind.
+        let mut value = Zeroizing::new(String::with_capacity(manifest.length));
+        for (index, chunk_version) in manifest.chunk_versions.iter().enumerate() {
+            let chunk = self
+                .get_secret_with_version(&chunk_name(secret_name, index), chunk_version)
+                .await?;
+            if value.len() + chunk.value().expose().len() > manifest.length {
+                return Err(corrupted(format!(
+                    "the chunks exceed the expected {} bytes",
+                    manifest.length
+                )));
+            }
+            value.push_str(chunk.value().expose());
+        }
+
+        if value.len() != manifest.length {
+            return Err(corrupted(format!(
+                "expected {} bytes, reassembled {}",
+                manifest.length,
+                value.len()
+            )));
+        }
+        if sha256_hex(value.as_bytes()) != manifest.sha256 {
+            return Err(corrupted(
+                "the digest of the reassembled value does not match".to_owned(),
+            ));
+        }
+        Ok(SecretValue::new(std::mem::take(&mut *value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::test_utils::typed_secret_bundle;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    #[test]
+    fn split_chunks_on_char_boundaries() {
+        assert!(split_chunks("", 4).is_empty());
+        assert_eq!(vec!["abcd", "ef"], split_chunks("abcdef", 4));
+        // 'é' takes two bytes, and must not be split across chunks.
+        assert_eq!(vec!["abc", "éd"], split_chunks("abcéd", 4));
+    }
+
+    #[tokio::test]
+    async fn set_and_get_chunked_secret() {
+        let value = "0123456789".repeat(3000);
+        let chunks = split_chunks(&value, MAX_CHUNK_SIZE);
+        assert_eq!(2, chunks.len());
+
+        let mut mocks = Vec::new();
+        for (index, chunk) in chunks.iter().enumerate() {
+            let chunk_name = format!("big-secret--{}", index);
+            let version = format!("VERSION_{}", index);
+            mocks.push(
+                mock("PUT", format!("/secrets/{}", chunk_name).as_str())
+                    .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+                    .with_body(typed_secret_bundle(&chunk_name, &version, chunk, CHUNK_CONTENT_TYPE).to_string())
+                    .with_status(200)
+                    .create(),
+            );
+            mocks.push(
+                mock("GET", format!("/secrets/{}/{}", chunk_name, version).as_str())
+                    .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+                    .with_body(typed_secret_bundle(&chunk_name, &version, chunk, CHUNK_CONTENT_TYPE).to_string())
+                    .with_status(200)
+                    .create(),
+            );
+        }
+        let manifest = serde_json::to_string(&ChunkedSecretManifest {
+            length: value.len(),
+            sha256: sha256_hex(value.as_bytes()),
+            chunk_versions: vec!["VERSION_0".to_owned(), "VERSION_1".to_owned()],
+        })
+        .unwrap();
+        let manifest_put = mock("PUT", "/secrets/big-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::PartialJson(json!({
+                "value": manifest,
+                "contentType": CHUNKED_MANIFEST_CONTENT_TYPE
+            })))
+            .with_status(200)
+            .create();
+        let _manifest_get = mock("GET", "/secrets/big-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                typed_secret_bundle("big-secret", "VERSION", &manifest, CHUNKED_MANIFEST_CONTENT_TYPE).to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        client.set_chunked_secret("big-secret", &value).await.unwrap();
+        manifest_put.assert();
+
+        let reassembled = client.get_chunked_secret("big-secret").await.unwrap();
+        assert_eq!(value, reassembled.expose());
+    }
+
+    #[tokio::test]
+    async fn get_chunked_secret_detects_digest_mismatch() {
+        let manifest = json!({
+            "length": 5,
+            "sha256": sha256_hex(b"hello"),
+            "chunk_versions": ["VERSION_0"]
+        })
+        .to_string();
+        let _m = mock("GET", "/secrets/tampered-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                typed_secret_bundle("tampered-secret", "VERSION", &manifest, CHUNKED_MANIFEST_CONTENT_TYPE).to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m2 = mock("GET", "/secrets/tampered-secret--0/VERSION_0")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(typed_secret_bundle("tampered-secret--0", "VERSION_0", "jello", CHUNK_CONTENT_TYPE).to_string())
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.get_chunked_secret("tampered-secret").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::ChunkedSecretCorrupted { .. }));
+    }
+
+    #[tokio::test]
+    async fn get_chunked_secret_rejects_oversized_manifest() {
+        let manifest = json!({
+            "length": usize::MAX,
+            "sha256": sha256_hex(b"hello"),
+            "chunk_versions": ["VERSION_0"]
+        })
+        .to_string();
+        let _m = mock("GET", "/secrets/oversized-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(
+                typed_secret_bundle("oversized-secret", "VERSION", &manifest, CHUNKED_MANIFEST_CONTENT_TYPE)
+                    .to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.get_chunked_secret("oversized-secret").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::ChunkedSecretCorrupted { .. }));
+    }
+
+    #[tokio::test]
+    async fn set_chunked_secret_rejects_names_too_long_for_chunks() {
+        let mut client = mock_client!(&"test-keyvault");
+
+        // Fits as a secret name, but not once the chunk suffix is appended.
+        let secret_name = "a".repeat(126);
+        let err = client
+            .set_chunked_secret(&secret_name, &"0123456789".repeat(3000))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidSecret { .. }));
+    }
+}
@@ -17,7 +17,7 @@ pub(crate) const API_VERSION: &str = "7.0";
 /// use azure_sdk_keyvault::KeyVaultClient;
 /// let client = KeyVaultClient::new(&"{client_id}", &"{client_secret}", &"{tenant_id}", &"test-keyvault");
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct KeyVaultClient<'a> {
     pub(crate) aad_client_id: &'a str,
     pub(crate) aad_client_secret: &'a str,
@@ -160,7 +160,7 @@ impl<'a> KeyVaultClient<'a> {
         )
         .await
         .with_context(|| "Failed to authenticate to Azure Active Directory")
-        .map_err(|e| KeyVaultError::AuthorizationError(e))?;
+        .map_err(KeyVaultError::AuthorizationError)?;
         self.token = Some(token.access_token().clone());
         self.token_expiration = Some(token.expires_on);
         Ok(())
@@ -179,10 +179,11 @@ impl<'a> KeyVaultClient<'a> {
             .await
             .unwrap();
         let body = resp.text().await.unwrap();
+        check_error(&body)?;
         Ok(body)
     }
 
-    pub(crate) async fn put_authed(&mut self, uri: String, body: String) -> Result<String, KeyVaultError> {
+    pub(crate) async fn put_authed(&mut self, uri: String, body: Vec<u8>) -> Result<String, KeyVaultError> {
         self.refresh_token().await?;
 
         let resp = reqwest::Client::new()
@@ -197,10 +198,15 @@ impl<'a> KeyVaultClient<'a> {
             .await
             .unwrap();
         let body = resp.text().await.unwrap();
+        check_error(&body)?;
         Ok(body)
     }
 
-    pub(crate) async fn post_authed(&mut self, uri: String, json_body: Option<String>) -> Result<String, KeyVaultError> {
+    pub(crate) async fn post_authed(
+        &mut self,
+        uri: String,
+        json_body: Option<String>,
+    ) -> Result<String, KeyVaultError> {
         self.refresh_token().await?;
 
         let mut req = reqwest::Client::new().post(&uri).header(
@@ -217,6 +223,7 @@ impl<'a> KeyVaultClient<'a> {
         let resp = req.send().await.unwrap();
 
         let body = resp.text().await.unwrap();
+        check_error(&body)?;
         Ok(body)
     }
 
@@ -236,15 +243,7 @@ impl<'a> KeyVaultClient<'a> {
             .unwrap();
 
         let body = resp.text().await.unwrap();
-
-        let body_serialized = serde_json::from_str::<serde_json::Value>(&body).unwrap();
-        if let Some(err) = body_serialized.get("error") {
-            return Err(KeyVaultError::GeneralError(
-                err.get("message")
-                    .expect("Received an error accessing the Key Vault, which could not be parsed as expected.")
-                    .to_string(),
-            ));
-        }
+        check_error(&body)?;
 
         Ok(body)
     }
@@ -263,6 +262,60 @@ impl<'a> KeyVaultClient<'a> {
             .await
             .unwrap();
         let body = resp.text().await.unwrap();
+        check_error(&body)?;
         Ok(body)
     }
 }
+
+/// Fails with the message of the error described by a response body, if any.
+/// Errors with a `*NotFound` code, such as `SecretNotFound`, are reported as [`KeyVaultError::NotFound`].
+pub(crate) fn check_error(body: &str) -> Result<(), KeyVaultError> {
+    if let Ok(body_serialized) = serde_json::from_str::<serde_json::Value>(body) {
+        if let Some(err) = body_serialized.get("error") {
+            let message = match err.get("message") {
+                Some(message) => message.to_string(),
+                None => format!("Received an error accessing the Key Vault: {}", err),
+            };
+            let code = err.get("code").and_then(serde_json::Value::as_str).unwrap_or_default();
+            // Reading a disabled version is forbidden, with the reason given by the inner error.
+            let inner_code = err
+                .pointer("/innererror/code")
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default();
+            return Err(if code.ends_with("NotFound") {
+                KeyVaultError::NotFound(message)
+            } else if inner_code == "SecretDisabled" {
+                KeyVaultError::SecretDisabled(message)
+            } else {
+                KeyVaultError::GeneralError(message)
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_error_reports_the_error_message() {
+        assert!(check_error(r#"{"value": "secret-value"}"#).is_ok());
+        assert!(check_error("").is_ok());
+
+        let err = check_error(r#"{"error": {"code": "Forbidden", "message": "Access denied"}}"#).unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("Access denied")));
+
+        let err = check_error(r#"{"error": {"code": "Forbidden"}}"#).unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("Forbidden")));
+
+        let err = check_error(r#"{"error": {"code": "SecretNotFound", "message": "Secret not found"}}"#).unwrap_err();
+        assert!(matches!(err, KeyVaultError::NotFound(_)));
+
+        let err = check_error(
+            r#"{"error": {"code": "Forbidden", "message": "Operation get is not allowed on a disabled secret.", "innererror": {"code": "SecretDisabled"}}}"#,
+        )
+        .unwrap_err();
+        assert!(matches!(err, KeyVaultError::SecretDisabled(_)));
+    }
+}
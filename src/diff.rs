@@ -0,0 +1,455 @@
+use crate::migration::SecretProperty;
+use crate::{random_bytes, KeyVaultClient, KeyVaultError};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use getset::Getters;
+use hmac::{Hmac, Mac, NewMac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use zeroize::Zeroizing;
+
+const SNAPSHOT_KEY_LENGTH: usize = 32;
+/// Authenticated in place of a value to tell which key a snapshot was taken with.
+const KEY_CHECK_MESSAGE: &[u8] = b"azure-sdk-keyvault-snapshot-key-check";
+
+/// The key of the value digests in vault snapshots.
+///
+/// The key is never stored in a snapshot. Keep it apart from the snapshots it was used for:
+/// anyone holding both can test guesses of the secret values against the digests.
+/// It is redacted from `Debug` output, and its memory is zeroed when dropped.
+#[derive(Clone)]
+pub struct SnapshotKey(Zeroizing<Vec<u8>>);
+
+impl SnapshotKey {
+    /// Creates a new random key.
+    pub fn generate() -> Self {
+        Self(Zeroizing::new(random_bytes(SNAPSHOT_KEY_LENGTH)))
+    }
+
+    /// Creates a key from bytes kept elsewhere, such as in another Key Vault secret.
+    pub fn from_bytes(key: &[u8]) -> Self {
+        Self(Zeroizing::new(key.to_vec()))
+    }
+
+    /// Returns the bytes of the key, to store it.
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Hex-encoded HMAC-SHA256 of a message.
+    fn authenticate(&self, message: &[u8]) -> String {
+        // HMAC accepts keys of any length.
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0).unwrap();
+        mac.update(message);
+        hex::encode(mac.finalize().into_bytes())
+    }
+}
+
+impl fmt::Debug for SnapshotKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SnapshotKey([REDACTED])")
+    }
+}
+
+/// The state of a secret at the time of a snapshot. Its value is only kept as a keyed digest.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct SecretSnapshot {
+    name: String,
+    /// Hex-encoded HMAC-SHA256 of the value of the latest version, keyed with the [`SnapshotKey`]
+    /// of the snapshot.
+    /// Absent when the latest version is disabled, as its value can not be read.
+    value_digest: Option<String>,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+    enabled: bool,
+    time_not_before: Option<DateTime<Utc>>,
+    time_expires: Option<DateTime<Utc>>,
+    time_updated: DateTime<Utc>,
+}
+
+impl SecretSnapshot {
+    fn differing_properties(&self, other: &SecretSnapshot) -> Vec<SecretProperty> {
+        let mut properties = Vec::new();
+        if self.value_digest != other.value_digest {
+            properties.push(SecretProperty::Value);
+        }
+        if self.content_type != other.content_type {
+            properties.push(SecretProperty::ContentType);
+        }
+        if self.tags != other.tags {
+            properties.push(SecretProperty::Tags);
+        }
+        if self.enabled != other.enabled {
+            properties.push(SecretProperty::Enabled);
+        }
+        if self.time_not_before != other.time_not_before {
+            properties.push(SecretProperty::NotBefore);
+        }
+        if self.time_expires != other.time_expires {
+            properties.push(SecretProperty::Expires);
+        }
+        properties
+    }
+}
+
+/// The state of every secret in a Key Vault at a point in time, which can be saved as JSON and
+/// compared with the vault later on.
+#[derive(Debug, Clone, Getters, Serialize, Deserialize)]
+#[getset(get = "pub")]
+pub struct VaultSnapshot {
+    vault_url: String,
+    time_created: DateTime<Utc>,
+    /// Hex-encoded HMAC-SHA256 of a fixed message with the [`SnapshotKey`] of the snapshot, which tells
+    /// whether two snapshots were taken with the same key without revealing it.
+    key_check: String,
+    /// Secrets, sorted by name.
+    secrets: Vec<SecretSnapshot>,
+}
+
+impl VaultSnapshot {
+    /// Compares this snapshot with a later one. Both must have been taken with the same key,
+    /// as done by [`diff_against_snapshot`](KeyVaultClient::diff_against_snapshot).
+    pub fn diff(&self, other: &VaultSnapshot) -> Result<VaultDiff, KeyVaultError> {
+        if self.key_check != other.key_check {
+            return Err(KeyVaultError::GeneralError(
+                "The values of snapshots taken with different keys can not be compared".to_owned(),
+            ));
+        }
+
+        let first = self.secrets.iter().map(|s| (&s.name, s)).collect::<BTreeMap<_, _>>();
+        let second = other.secrets.iter().map(|s| (&s.name, s)).collect::<BTreeMap<_, _>>();
+        let mut diff = VaultDiff::default();
+        for (name, first_secret) in &first {
+            match second.get(name) {
+                None => diff.only_in_first.push((*name).clone()),
+                Some(second_secret) => {
+                    let properties = first_secret.differing_properties(second_secret);
+                    if !properties.is_empty() {
+                        diff.different.push(SecretDiff {
+                            name: (*name).clone(),
+                            properties,
+                            first: (*first_secret).clone(),
+                            second: (*second_secret).clone(),
+                        });
+                    }
+                }
+            }
+        }
+        diff.only_in_second = second
+            .keys()
+            .filter(|name| !first.contains_key(*name))
+            .map(|name| (*name).clone())
+            .collect();
+        Ok(diff)
+    }
+}
+
+/// A secret which exists on both sides of a diff, but differs.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct SecretDiff {
+    name: String,
+    properties: Vec<SecretProperty>,
+    first: SecretSnapshot,
+    second: SecretSnapshot,
+}
+
+/// The differences between two vaults, or a vault and a snapshot of it. Never holds any secret value.
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct VaultDiff {
+    only_in_first: Vec<String>,
+    only_in_second: Vec<String>,
+    different: Vec<SecretDiff>,
+}
+
+impl VaultDiff {
+    /// Whether both sides hold the same secrets.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_first.is_empty() && self.only_in_second.is_empty() && self.different.is_empty()
+    }
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Takes a snapshot of every secret in the Key Vault, getting up to `concurrency` secrets at a time.
+    /// Values are kept as digests keyed with `key`, which is needed again to compare the snapshot later on.
+    ///
+    /// This operation requires the secrets/list and secrets/get permissions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::diff::SnapshotKey;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let key = SnapshotKey::generate();
+    ///     let snapshot = client.snapshot_vault(&key, 8).await.unwrap();
+    ///     std::fs::write("vault-snapshot.json", serde_json::to_string(&snapshot).unwrap()).unwrap();
+    ///     std::fs::write("/secure/vault-snapshot.key", key.expose()).unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn snapshot_vault(
+        &mut self,
+        key: &SnapshotKey,
+        concurrency: usize,
+    ) -> Result<VaultSnapshot, KeyVaultError> {
+        let secrets = self.list_secrets().await?;
+        // Every request below goes through a copy of the client, which shares the token refreshed here.
+        self.refresh_token().await?;
+
+        let client = &*self;
+        let mut secrets = stream::iter(secrets)
+            .map(|listed| async move {
+                let mut snapshot = SecretSnapshot {
+                    name: listed.name().clone(),
+                    value_digest: None,
+                    content_type: listed.content_type().clone(),
+                    tags: listed.tags().clone(),
+                    enabled: *listed.enabled(),
+                    time_not_before: *listed.time_not_before(),
+                    time_expires: *listed.time_expires(),
+                    time_updated: *listed.time_updated(),
+                };
+                if snapshot.enabled {
+                    let secret = client.clone().get_secret(listed.name()).await?;
+                    snapshot.value_digest = Some(key.authenticate(secret.value().expose().as_bytes()));
+                }
+                Ok::<_, KeyVaultError>(snapshot)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+        secrets.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(VaultSnapshot {
+            vault_url: self.keyvault_endpoint.clone(),
+            time_created: Utc::now(),
+            key_check: key.authenticate(KEY_CHECK_MESSAGE),
+            secrets,
+        })
+    }
+
+    /// Compares a snapshot taken earlier with the current state of the Key Vault,
+    /// getting up to `concurrency` secrets at a time. `key` must be the key the snapshot was taken with.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::diff::{SnapshotKey, VaultSnapshot};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let snapshot: VaultSnapshot =
+    ///         serde_json::from_str(&std::fs::read_to_string("vault-snapshot.json").unwrap()).unwrap();
+    ///     let key = SnapshotKey::from_bytes(&std::fs::read("/secure/vault-snapshot.key").unwrap());
+    ///     let diff = client.diff_against_snapshot(&snapshot, &key, 8).await.unwrap();
+    ///     println!("{}", serde_json::to_string_pretty(&diff).unwrap());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn diff_against_snapshot(
+        &mut self,
+        snapshot: &VaultSnapshot,
+        key: &SnapshotKey,
+        concurrency: usize,
+    ) -> Result<VaultDiff, KeyVaultError> {
+        let current = self.snapshot_vault(key, concurrency).await?;
+        snapshot.diff(&current)
+    }
+}
+
+/// Compares the secrets of two Key Vaults, getting up to `concurrency` secrets at a time from each.
+///
+/// Values are compared by their digests, and never appear in the diff.
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::diff::diff_vaults;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let mut staging = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"STAGING_KEYVAULT_NAME",
+///     );
+///     let mut production = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"PRODUCTION_KEYVAULT_NAME",
+///     );
+///     let diff = diff_vaults(&mut staging, &mut production, 8).await.unwrap();
+///     for secret in diff.different() {
+///         println!("{}: {:?}", secret.name(), secret.properties());
+///     }
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+pub async fn diff_vaults(
+    first: &mut KeyVaultClient<'_>,
+    second: &mut KeyVaultClient<'_>,
+    concurrency: usize,
+) -> Result<VaultDiff, KeyVaultError> {
+    let key = SnapshotKey::generate();
+    let first = first.snapshot_vault(&key, concurrency).await?;
+    let second = second.snapshot_vault(&key, concurrency).await?;
+    first.diff(&second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    fn mock_vault(prefix: &str, secrets: &[(&str, &str, &str)]) -> Vec<mockito::Mock> {
+        let listed = secrets
+            .iter()
+            .map(|(name, _, owner)| {
+                json!({
+                    "id": format!("https://test-keyvault.vault.azure.net/secrets/{}", name),
+                    "tags": { "owner": owner },
+                    "attributes": { "enabled": true, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut mocks = vec![mock("GET", format!("{}/secrets", prefix).as_str())
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": listed, "nextLink": null }).to_string())
+            .with_status(200)
+            .create()];
+        for (name, value, owner) in secrets {
+            mocks.push(
+                mock("GET", format!("{}/secrets/{}/", prefix, name).as_str())
+                    .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+                    .with_body(
+                        json!({
+                            "value": value,
+                            "id": format!("https://test-keyvault.vault.azure.net/secrets/{}/VERSION", name),
+                            "tags": { "owner": owner },
+                            "attributes": {
+                                "enabled": true,
+                                "created": 1_600_000_000,
+                                "updated": 1_600_000_000,
+                                "recoveryLevel": "Recoverable+Purgeable"
+                            }
+                        })
+                        .to_string(),
+                    )
+                    .with_status(200)
+                    .create(),
+            );
+        }
+        mocks
+    }
+
+    #[tokio::test]
+    async fn diff_two_vaults() {
+        let _first = mock_vault(
+            "/first",
+            &[
+                ("same-secret", "same-value", "team-a"),
+                ("changed-secret", "first-value", "team-a"),
+                ("first-only-secret", "value", "team-a"),
+            ],
+        );
+        let _second = mock_vault(
+            "/second",
+            &[
+                ("same-secret", "same-value", "team-a"),
+                ("changed-secret", "second-value", "team-b"),
+                ("second-only-secret", "value", "team-a"),
+            ],
+        );
+
+        let mut first = mock_client!(&"first-keyvault");
+        first.keyvault_endpoint = format!("{}/first", mockito::server_url());
+        let mut second = mock_client!(&"second-keyvault");
+        second.keyvault_endpoint = format!("{}/second", mockito::server_url());
+
+        let diff = diff_vaults(&mut first, &mut second, 2).await.unwrap();
+        assert_eq!(&vec!["first-only-secret".to_owned()], diff.only_in_first());
+        assert_eq!(&vec!["second-only-secret".to_owned()], diff.only_in_second());
+        assert_eq!(1, diff.different().len());
+        assert_eq!("changed-secret", diff.different()[0].name());
+        assert_eq!(
+            &vec![SecretProperty::Value, SecretProperty::Tags],
+            diff.different()[0].properties()
+        );
+
+        let json = serde_json::to_string(&diff).unwrap();
+        assert!(!json.contains("first-value"));
+        assert!(!json.contains("second-value"));
+    }
+
+    #[test]
+    fn snapshot_key_authenticates_with_hmac_sha256() {
+        // RFC 4231, test case 2.
+        let key = SnapshotKey::from_bytes(b"Jefe");
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            key.authenticate(b"what do ya want for nothing?")
+        );
+        // RFC 4231, test case 6, with a key longer than a block.
+        let key = SnapshotKey::from_bytes(&[0xaa; 131]);
+        assert_eq!(
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54",
+            key.authenticate(b"Test Using Larger Than Block-Size Key - Hash Key First")
+        );
+        assert_eq!("SnapshotKey([REDACTED])", format!("{:?}", key));
+    }
+
+    #[tokio::test]
+    async fn diff_against_snapshot_requires_its_key() {
+        let _vault = mock_vault("/snapshot", &[("snapshot-secret", "snapshot-value", "team-a")]);
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/snapshot", mockito::server_url());
+
+        let key = SnapshotKey::generate();
+        let snapshot = client.snapshot_vault(&key, 2).await.unwrap();
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(!json.contains(&hex::encode(key.expose())));
+        assert!(!json.contains("snapshot-value"));
+
+        let snapshot = serde_json::from_str::<VaultSnapshot>(&json).unwrap();
+        assert!(client
+            .diff_against_snapshot(&snapshot, &key, 2)
+            .await
+            .unwrap()
+            .is_empty());
+        let err = client
+            .diff_against_snapshot(&snapshot, &SnapshotKey::generate(), 2)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{KeyVaultClient, KeyVaultError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::error::Category;
+use zeroize::Zeroizing;
+
+pub(crate) const JSON_CONTENT_TYPE: &str = "application/json";
+
+/// Content types whose values are stored as plain text rather than base64.
+/// Matches how Key Vault itself stores certificate-backed secrets (PKCS#12 as base64, PEM as text).
+const TEXT_CONTENT_TYPES: &[&str] = &[JSON_CONTENT_TYPE, "application/x-pem-file", "application/xml"];
+
+/// The media type of a content type, without parameters and in lowercase.
+fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap().trim().to_lowercase()
+}
+
+fn is_text_content_type(content_type: &str) -> bool {
+    let media_type = media_type(content_type);
+    media_type.starts_with("text/") || TEXT_CONTENT_TYPES.contains(&media_type.as_str())
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Gets the latest version of a secret holding a JSON document, and deserializes it.
+    ///
+    /// Deserialization failures are reported as
+    /// [`SecretDeserializationError`](KeyVaultError::SecretDeserializationError), which only carries
+    /// the position of the failure and never any part of the secret value.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use serde::Deserialize;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct ConnectionInfo {
+    ///     host: String,
+    ///     password: String,
+    /// }
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let connection_info: ConnectionInfo = client.get_secret_as(&"SECRET_NAME").await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn get_secret_as<T: DeserializeOwned>(&mut self, secret_name: &str) -> Result<T, KeyVaultError> {
+        let secret = self.get_secret(secret_name).await?;
+        serde_json::from_str(secret.value().expose()).map_err(|e| {
+            // The `Display` of serde errors can quote parts of the input, so only the position is kept.
+            let category = match e.classify() {
+                Category::Io => "I/O",
+                Category::Syntax => "syntax",
+                Category::Data => "data",
+                Category::Eof => "unexpected end of input",
+            };
+            KeyVaultError::SecretDeserializationError {
+                secret_name: secret_name.to_owned(),
+                category: category.to_owned(),
+                line: e.line(),
+                column: e.column(),
+            }
+        })
+    }
+
+    /// Serializes a value to JSON and sets it as the value of a secret, with the `application/json` content type.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use serde_json::json;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client
+    ///         .set_secret_json(&"SECRET_NAME", &json!({ "host": "db.contoso.com", "password": "hunter2" }))
+    ///         .await
+    ///         .unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_json<T: Serialize + ?Sized>(
+        &mut self,
+        secret_name: &str,
+        value: &T,
+    ) -> Result<(), KeyVaultError> {
+        let json = Zeroizing::new(serde_json::to_string(value).map_err(|e| {
+            KeyVaultError::GeneralError(format!(
+                "Failed to serialize the value of secret {}: {}",
+                secret_name, e
+            ))
+        })?);
+        self.set_secret_with_content_type(secret_name, &json, Some(JSON_CONTENT_TYPE))
+            .await
+    }
+
+    /// Sets binary data as the value of a secret, tagged with the given content type (e.g. `application/x-pkcs12`).
+    ///
+    /// Data is stored base64-encoded, except for text content types (`text/*`, `application/json`,
+    /// `application/x-pem-file`, `application/xml`) which are stored as-is and must be valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let keystore = std::fs::read("keystore.p12").unwrap();
+    ///     client
+    ///         .set_secret_bytes(&"SECRET_NAME", &keystore, &"application/x-pkcs12")
+    ///         .await
+    ///         .unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_bytes(
+        &mut self,
+        secret_name: &str,
+        value: &[u8],
+        content_type: &str,
+    ) -> Result<(), KeyVaultError> {
+        let encoded = if is_text_content_type(content_type) {
+            let text = std::str::from_utf8(value).map_err(|_| {
+                KeyVaultError::GeneralError(format!(
+                    "The value of secret {} is not valid UTF-8, as required by content type {}",
+                    secret_name, content_type
+                ))
+            })?;
+            Zeroizing::new(text.to_owned())
+        } else {
+            Zeroizing::new(base64::encode(value))
+        };
+        self.set_secret_with_content_type(secret_name, &encoded, Some(content_type))
+            .await
+    }
+
+    /// Gets the latest version of a secret holding binary data, decoding it according to its stored content type.
+    ///
+    /// Fails with [`ContentTypeMismatch`](KeyVaultError::ContentTypeMismatch) if the stored content type
+    /// does not match `expected_content_type` (parameters and case are ignored).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let keystore = client
+    ///         .get_secret_bytes(&"SECRET_NAME", &"application/x-pkcs12")
+    ///         .await
+    ///         .unwrap();
+    ///     std::fs::write("keystore.p12", &*keystore).unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn get_secret_bytes(
+        &mut self,
+        secret_name: &str,
+        expected_content_type: &str,
+    ) -> Result<Zeroizing<Vec<u8>>, KeyVaultError> {
+        let secret = self.get_secret(secret_name).await?;
+        let content_type = match secret.content_type() {
+            Some(content_type) if media_type(content_type) == media_type(expected_content_type) => content_type,
+            actual => {
+                return Err(KeyVaultError::ContentTypeMismatch {
+                    secret_name: secret_name.to_owned(),
+                    expected: expected_content_type.to_owned(),
+                    actual: actual.clone(),
+                })
+            }
+        };
+
+        if is_text_content_type(content_type) {
+            return Ok(Zeroizing::new(secret.value().expose().as_bytes().to_vec()));
+        }
+        base64::decode(secret.value().expose())
+            .map(Zeroizing::new)
+            // The decode error quotes the offending byte, so it is not passed along.
+            .map_err(|_| KeyVaultError::SecretDecodingError {
+                secret_name: secret_name.to_owned(),
+                content_type: content_type.to_owned(),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::API_VERSION;
+    use crate::test_utils::typed_secret_bundle;
+    use crate::KeyVaultError;
+
+    use mockito::{mock, Matcher};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ConnectionInfo {
+        host: String,
+        port: u16,
+    }
+
+    fn mock_secret_value(secret_name: &str, value: &str, content_type: &str) -> mockito::Mock {
+        mock("GET", format!("/secrets/{}/", secret_name).as_str())
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(typed_secret_bundle(secret_name, "VERSION", value, content_type).to_string())
+            .with_status(200)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn get_secret_as() {
+        let _m = mock_secret_value(
+            "json-secret",
+            r#"{"host":"db.contoso.com","port":5432}"#,
+            "application/json",
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let connection_info: ConnectionInfo = client.get_secret_as("json-secret").await.unwrap();
+        assert_eq!(
+            ConnectionInfo {
+                host: "db.contoso.com".to_owned(),
+                port: 5432
+            },
+            connection_info
+        );
+    }
+
+    #[tokio::test]
+    async fn get_secret_as_does_not_echo_value() {
+        let _m = mock_secret_value(
+            "malformed-json-secret",
+            r#"{"host":"hunter2-db","port":"hunter2"}"#,
+            "application/json",
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client
+            .get_secret_as::<ConnectionInfo>("malformed-json-secret")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::SecretDeserializationError { .. }));
+        assert!(!err.to_string().contains("hunter2"));
+        assert!(!format!("{:?}", err).contains("hunter2"));
+    }
+
+    #[tokio::test]
+    async fn set_and_get_secret_bytes() {
+        let m = mock("PUT", "/secrets/binary-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({
+                "value": "AAEC/w==",
+                "contentType": "application/x-pkcs12"
+            })))
+            .with_status(200)
+            .create();
+        let _m2 = mock_secret_value("binary-secret", "AAEC/w==", "application/x-pkcs12");
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        client
+            .set_secret_bytes("binary-secret", &[0x00, 0x01, 0x02, 0xff], "application/x-pkcs12")
+            .await
+            .unwrap();
+        m.assert();
+
+        let value = client
+            .get_secret_bytes("binary-secret", "Application/X-PKCS12")
+            .await
+            .unwrap();
+        assert_eq!(vec![0x00, 0x01, 0x02, 0xff], *value);
+
+        let err = client
+            .get_secret_bytes("binary-secret", "application/octet-stream")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::ContentTypeMismatch { .. }));
+    }
+
+    #[tokio::test]
+    async fn set_secret_json() {
+        let m = mock("PUT", "/secrets/json-secret-to-set")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({
+                "value": r#"{"host":"db.contoso.com","port":5432}"#,
+                "contentType": "application/json"
+            })))
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        client
+            .set_secret_json(
+                "json-secret-to-set",
+                &ConnectionInfo {
+                    host: "db.contoso.com".to_owned(),
+                    port: 5432,
+                },
+            )
+            .await
+            .unwrap();
+        m.assert();
+    }
+}
@@ -0,0 +1,334 @@
+use crate::{KeyVaultError, KeyVaultIdentifier};
+use chrono::{DateTime, TimeZone, Utc};
+use getset::Getters;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use std::convert::TryFrom;
+
+/// The data of an event about a secret, key or certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Deserialize)]
+#[getset(get = "pub")]
+pub struct KeyVaultObjectEventData {
+    /// The identifier of the version the event is about.
+    #[serde(rename = "Id", deserialize_with = "deserialize_identifier")]
+    identifier: KeyVaultIdentifier,
+    #[serde(rename = "VaultName")]
+    vault_name: String,
+    /// `Secret`, `Key` or `Certificate`.
+    #[serde(rename = "ObjectType")]
+    object_type: String,
+    #[serde(rename = "ObjectName")]
+    object_name: String,
+    #[serde(rename = "Version", default)]
+    version: Option<String>,
+    #[serde(rename = "NBF", default, deserialize_with = "deserialize_timestamp")]
+    not_before: Option<DateTime<Utc>>,
+    #[serde(rename = "EXP", default, deserialize_with = "deserialize_timestamp")]
+    expires: Option<DateTime<Utc>>,
+}
+
+/// The data of an event about a whole vault.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Deserialize)]
+#[getset(get = "pub")]
+pub struct KeyVaultVaultEventData {
+    /// The URL of the vault.
+    #[serde(rename = "Id")]
+    id: String,
+    #[serde(rename = "VaultName")]
+    vault_name: String,
+}
+
+/// An event published by Key Vault through Event Grid.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyVaultEvent {
+    SecretNewVersionCreated(KeyVaultObjectEventData),
+    SecretNearExpiry(KeyVaultObjectEventData),
+    SecretExpired(KeyVaultObjectEventData),
+    KeyNewVersionCreated(KeyVaultObjectEventData),
+    KeyNearExpiry(KeyVaultObjectEventData),
+    KeyExpired(KeyVaultObjectEventData),
+    CertificateNewVersionCreated(KeyVaultObjectEventData),
+    CertificateNearExpiry(KeyVaultObjectEventData),
+    CertificateExpired(KeyVaultObjectEventData),
+    VaultAccessPolicyChanged(KeyVaultVaultEventData),
+    /// An event of a type this crate does not know about.
+    Other {
+        event_type: String,
+        data: Value,
+    },
+}
+
+impl KeyVaultEvent {
+    fn from_parts(event_type: String, data: Value) -> Result<Self, serde_json::Error> {
+        let object =
+            |variant: fn(KeyVaultObjectEventData) -> KeyVaultEvent| serde_json::from_value(data.clone()).map(variant);
+        match event_type.strip_prefix("Microsoft.KeyVault.") {
+            Some("SecretNewVersionCreated") => object(KeyVaultEvent::SecretNewVersionCreated),
+            Some("SecretNearExpiry") => object(KeyVaultEvent::SecretNearExpiry),
+            Some("SecretExpired") => object(KeyVaultEvent::SecretExpired),
+            Some("KeyNewVersionCreated") => object(KeyVaultEvent::KeyNewVersionCreated),
+            Some("KeyNearExpiry") => object(KeyVaultEvent::KeyNearExpiry),
+            Some("KeyExpired") => object(KeyVaultEvent::KeyExpired),
+            Some("CertificateNewVersionCreated") => object(KeyVaultEvent::CertificateNewVersionCreated),
+            Some("CertificateNearExpiry") => object(KeyVaultEvent::CertificateNearExpiry),
+            Some("CertificateExpired") => object(KeyVaultEvent::CertificateExpired),
+            Some("VaultAccessPolicyChanged") => {
+                serde_json::from_value(data).map(KeyVaultEvent::VaultAccessPolicyChanged)
+            }
+            _ => Ok(KeyVaultEvent::Other { event_type, data }),
+        }
+    }
+
+    /// The data of the event, if it is about a secret, key or certificate.
+    pub fn object_data(&self) -> Option<&KeyVaultObjectEventData> {
+        match self {
+            KeyVaultEvent::SecretNewVersionCreated(data)
+            | KeyVaultEvent::SecretNearExpiry(data)
+            | KeyVaultEvent::SecretExpired(data)
+            | KeyVaultEvent::KeyNewVersionCreated(data)
+            | KeyVaultEvent::KeyNearExpiry(data)
+            | KeyVaultEvent::KeyExpired(data)
+            | KeyVaultEvent::CertificateNewVersionCreated(data)
+            | KeyVaultEvent::CertificateNearExpiry(data)
+            | KeyVaultEvent::CertificateExpired(data) => Some(data),
+            KeyVaultEvent::VaultAccessPolicyChanged(_) | KeyVaultEvent::Other { .. } => None,
+        }
+    }
+
+    /// The identifier of the secret, key or certificate version the event is about,
+    /// for example to get the new version of a secret with
+    /// [`get_secret_by_id`](crate::KeyVaultClient::get_secret_by_id).
+    pub fn identifier(&self) -> Option<&KeyVaultIdentifier> {
+        self.object_data().map(|data| &data.identifier)
+    }
+}
+
+/// An event, along with the envelope it was delivered in.
+/// Both the Event Grid and the CloudEvents 1.0 schemas are understood.
+///
+/// # Example
+///
+/// ```
+/// use azure_sdk_keyvault::events::{KeyVaultEvent, KeyVaultEventEnvelope};
+///
+/// let body = r#"[{
+///     "id": "00eccf70-95a7-4e7c-8299-2eb17ee9ad64",
+///     "topic": "/subscriptions/SUBSCRIPTION_ID/resourceGroups/RESOURCE_GROUP/providers/Microsoft.KeyVault/vaults/test-keyvault",
+///     "subject": "test-secret",
+///     "eventType": "Microsoft.KeyVault.SecretNewVersionCreated",
+///     "eventTime": "2020-09-13T12:26:40Z",
+///     "data": {
+///         "Id": "https://test-keyvault.vault.azure.net/secrets/test-secret/4387e9f3d6e14c459867679a90fd0f79",
+///         "VaultName": "test-keyvault",
+///         "ObjectType": "Secret",
+///         "ObjectName": "test-secret",
+///         "Version": "4387e9f3d6e14c459867679a90fd0f79",
+///         "NBF": null,
+///         "EXP": 1700000000
+///     },
+///     "dataVersion": "1",
+///     "metadataVersion": "1"
+/// }]"#;
+/// let events = KeyVaultEventEnvelope::parse_batch(body).unwrap();
+/// assert!(matches!(events[0].event(), KeyVaultEvent::SecretNewVersionCreated(_)));
+/// assert_eq!("test-secret", events[0].event().identifier().unwrap().name());
+/// ```
+#[derive(Debug, Clone, PartialEq, Getters, Deserialize)]
+#[serde(try_from = "RawEnvelope")]
+#[getset(get = "pub")]
+pub struct KeyVaultEventEnvelope {
+    id: String,
+    /// The resource ID of the vault which published the event:
+    /// `topic` in the Event Grid schema, `source` in the CloudEvents schema.
+    source: String,
+    subject: String,
+    event_type: String,
+    time: DateTime<Utc>,
+    event: KeyVaultEvent,
+}
+
+impl KeyVaultEventEnvelope {
+    /// Parses a delivery, holding either a single event or an array of events.
+    pub fn parse_batch(body: &str) -> Result<Vec<Self>, KeyVaultError> {
+        Self::parse_each(body)?.into_iter().collect()
+    }
+
+    /// Parses a delivery like [`parse_batch`](KeyVaultEventEnvelope::parse_batch), but each event on its own,
+    /// so that one malformed event does not fail the others. Only fails if the delivery is not JSON.
+    pub fn parse_each(body: &str) -> Result<Vec<Result<Self, KeyVaultError>>, KeyVaultError> {
+        let events = match serde_json::from_str::<Value>(body) {
+            Ok(Value::Array(events)) => events,
+            Ok(event) => vec![event],
+            Err(e) => return Err(KeyVaultError::InvalidEvent(e.to_string())),
+        };
+        Ok(events
+            .into_iter()
+            .map(|event| serde_json::from_value(event).map_err(|e| KeyVaultError::InvalidEvent(e.to_string())))
+            .collect())
+    }
+}
+
+#[derive(Deserialize)]
+struct RawEventGridEvent {
+    id: String,
+    topic: String,
+    subject: String,
+    #[serde(rename = "eventType")]
+    event_type: String,
+    #[serde(rename = "eventTime")]
+    event_time: DateTime<Utc>,
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Deserialize)]
+struct RawCloudEvent {
+    /// Only read to tell CloudEvents apart from Event Grid events.
+    #[allow(dead_code)]
+    specversion: String,
+    id: String,
+    source: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(rename = "type")]
+    event_type: String,
+    time: DateTime<Utc>,
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawEnvelope {
+    CloudEvent(RawCloudEvent),
+    EventGrid(RawEventGridEvent),
+}
+
+impl TryFrom<RawEnvelope> for KeyVaultEventEnvelope {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawEnvelope) -> Result<Self, Self::Error> {
+        let (id, source, subject, event_type, time, data) = match raw {
+            RawEnvelope::EventGrid(e) => (e.id, e.topic, e.subject, e.event_type, e.event_time, e.data),
+            RawEnvelope::CloudEvent(e) => (e.id, e.source, e.subject, e.event_type, e.time, e.data),
+        };
+        Ok(Self {
+            event: KeyVaultEvent::from_parts(event_type.clone(), data)?,
+            id,
+            source,
+            subject,
+            event_type,
+            time,
+        })
+    }
+}
+
+fn deserialize_identifier<'de, D>(deserializer: D) -> Result<KeyVaultIdentifier, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let identifier = String::deserialize(deserializer)?;
+    KeyVaultIdentifier::parse(&identifier).map_err(serde::de::Error::custom)
+}
+
+/// Reads a Unix timestamp, which Key Vault events hold either as a number or as a string.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds = match Value::deserialize(deserializer)? {
+        Value::Null => return Ok(None),
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    seconds
+        .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+        .map(Some)
+        .ok_or_else(|| serde::de::Error::custom("expected a Unix timestamp"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identifier::KeyVaultCollection;
+    use serde_json::json;
+
+    #[test]
+    fn parse_events() {
+        let data = |object_type: &str, collection: &str, name: &str| {
+            json!({
+                "Id": format!("https://test-keyvault.vault.azure.net/{}/{}/VERSION_1", collection, name),
+                "VaultName": "test-keyvault",
+                "ObjectType": object_type,
+                "ObjectName": name,
+                "Version": "VERSION_1",
+                "NBF": "1600000000",
+                "EXP": 1_700_000_000
+            })
+        };
+        let event_grid = json!([
+            {
+                "id": "EVENT_1",
+                "topic": "/subscriptions/SUBSCRIPTION_ID/providers/Microsoft.KeyVault/vaults/test-keyvault",
+                "subject": "test-secret",
+                "eventType": "Microsoft.KeyVault.SecretNearExpiry",
+                "eventTime": "2020-09-13T12:26:40Z",
+                "data": data("Secret", "secrets", "test-secret"),
+                "dataVersion": "1",
+                "metadataVersion": "1"
+            },
+            {
+                "id": "EVENT_2",
+                "topic": "/subscriptions/SUBSCRIPTION_ID/providers/Microsoft.KeyVault/vaults/test-keyvault",
+                "subject": "test-keyvault",
+                "eventType": "Microsoft.KeyVault.VaultAccessPolicyChanged",
+                "eventTime": "2020-09-13T12:26:40Z",
+                "data": { "Id": "https://test-keyvault.vault.azure.net", "VaultName": "test-keyvault" }
+            }
+        ]);
+        let events = KeyVaultEventEnvelope::parse_batch(&event_grid.to_string()).unwrap();
+        match events[0].event() {
+            KeyVaultEvent::SecretNearExpiry(data) => {
+                assert_eq!(KeyVaultCollection::Secrets, data.identifier().collection());
+                assert_eq!(Utc.timestamp_opt(1_600_000_000, 0).single(), *data.not_before());
+                assert_eq!(Utc.timestamp_opt(1_700_000_000, 0).single(), *data.expires());
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        assert!(matches!(events[1].event(), KeyVaultEvent::VaultAccessPolicyChanged(_)));
+        assert_eq!(None, events[1].event().identifier());
+
+        let cloud_event = json!({
+            "specversion": "1.0",
+            "id": "EVENT_3",
+            "source": "/subscriptions/SUBSCRIPTION_ID/providers/Microsoft.KeyVault/vaults/test-keyvault",
+            "subject": "test-key",
+            "type": "Microsoft.KeyVault.KeyNewVersionCreated",
+            "time": "2020-09-13T12:26:40Z",
+            "data": data("Key", "keys", "test-key")
+        });
+        let events = KeyVaultEventEnvelope::parse_batch(&cloud_event.to_string()).unwrap();
+        assert_eq!("EVENT_3", events[0].id());
+        let identifier = events[0].event().identifier().unwrap();
+        assert_eq!(KeyVaultCollection::Keys, identifier.collection());
+        assert_eq!(&Some("VERSION_1".to_owned()), identifier.version());
+
+        let unknown = json!({
+            "specversion": "1.0",
+            "id": "EVENT_4",
+            "source": "/subscriptions/SUBSCRIPTION_ID",
+            "type": "Microsoft.Storage.BlobCreated",
+            "time": "2020-09-13T12:26:40Z",
+            "data": {}
+        });
+        let events = KeyVaultEventEnvelope::parse_batch(&unknown.to_string()).unwrap();
+        assert!(matches!(events[0].event(), KeyVaultEvent::Other { .. }));
+
+        let malformed = json!({ "id": "EVENT_5" });
+        assert!(matches!(
+            KeyVaultEventEnvelope::parse_batch(&malformed.to_string()),
+            Err(KeyVaultError::InvalidEvent(_))
+        ));
+    }
+}
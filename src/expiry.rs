@@ -0,0 +1,202 @@
+use crate::{KeyVaultClient, KeyVaultError};
+use chrono::{DateTime, Duration, Utc};
+use getset::Getters;
+use serde::Serialize;
+use std::io::Write;
+
+/// The tag read to report who owns an expiring secret.
+pub const OWNER_TAG: &str = "owner";
+
+/// Why a secret version was reported by [`scan_expiring`](KeyVaultClient::scan_expiring).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryStatus {
+    /// The version expired before the scan.
+    Expired,
+    /// The version expires within the scanned window.
+    ExpiringSoon,
+    /// The version never expires.
+    NoExpiry,
+}
+
+/// An enabled secret version which has expired, is about to, or never will.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ExpiringSecret {
+    secret_name: String,
+    version: String,
+    status: ExpiryStatus,
+    time_expires: Option<DateTime<Utc>>,
+    /// The value of the [`OWNER_TAG`] tag of the version, if any.
+    owner: Option<String>,
+    content_type: Option<String>,
+}
+
+/// The result of [`scan_expiring`](KeyVaultClient::scan_expiring), which can be written as JSON with
+/// serde or as CSV with [`write_csv`](ExpiryReport::write_csv).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct ExpiryReport {
+    vault_url: String,
+    time_scanned: DateTime<Utc>,
+    /// The end of the scanned window.
+    time_window_end: DateTime<Utc>,
+    /// Expired versions first, then versions expiring soonest, then versions without expiry.
+    secrets: Vec<ExpiringSecret>,
+}
+
+impl ExpiryReport {
+    /// Writes the reported versions as CSV, with a header row.
+    pub fn write_csv<W: Write>(&self, writer: W) -> Result<(), KeyVaultError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for secret in &self.secrets {
+            writer.serialize(secret).map_err(std::io::Error::from)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Walks every version of every secret in the Key Vault, and reports the enabled versions
+    /// which have expired, expire within `within` from now, or have no expiry at all.
+    /// Disabled versions are left out: applications can not read them, so their expiry does not matter.
+    ///
+    /// This operation requires the secrets/list permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use chrono::Duration;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let report = client.scan_expiring(Duration::days(30)).await.unwrap();
+    ///     report.write_csv(std::io::stdout()).unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn scan_expiring(&mut self, within: Duration) -> Result<ExpiryReport, KeyVaultError> {
+        let time_scanned = Utc::now();
+        let time_window_end = time_scanned + within;
+
+        let mut secrets = Vec::new();
+        for secret in self.list_secrets().await? {
+            for version in self.get_secret_versions(secret.name()).await? {
+                if !*version.enabled() {
+                    continue;
+                }
+                let status = match version.time_expires() {
+                    None => ExpiryStatus::NoExpiry,
+                    Some(expires) if *expires <= time_scanned => ExpiryStatus::Expired,
+                    Some(expires) if *expires <= time_window_end => ExpiryStatus::ExpiringSoon,
+                    Some(_) => continue,
+                };
+                secrets.push(ExpiringSecret {
+                    secret_name: secret.name().clone(),
+                    version: version.version().clone().unwrap_or_default(),
+                    status,
+                    time_expires: *version.time_expires(),
+                    owner: version.tags().get(OWNER_TAG).cloned(),
+                    content_type: version.content_type().clone(),
+                });
+            }
+        }
+        secrets.sort_by(|a, b| {
+            (a.status, a.time_expires, &a.secret_name, &a.version).cmp(&(
+                b.status,
+                b.time_expires,
+                &b.secret_name,
+                &b.version,
+            ))
+        });
+
+        Ok(ExpiryReport {
+            vault_url: self.keyvault_endpoint.clone(),
+            time_scanned,
+            time_window_end,
+            secrets,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_get;
+
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn scan_expiring() {
+        let now = Utc::now().timestamp();
+        let versions = [
+            ("VERSION_1", true, Some(now - 86_400)),
+            ("VERSION_2", true, Some(now + 7 * 86_400)),
+            ("VERSION_3", true, Some(now + 365 * 86_400)),
+            ("VERSION_4", true, None),
+            ("VERSION_5", false, None),
+        ]
+        .iter()
+        .map(|(version, enabled, exp)| {
+            json!({
+                "id": format!("https://test-keyvault.vault.azure.net/secrets/db-password/{}", version),
+                "tags": { "owner": "team-a" },
+                "attributes": { "enabled": enabled, "exp": exp, "created": 1_600_000_000, "updated": 1_600_000_000 }
+            })
+        })
+        .collect::<Vec<_>>();
+        let _m1 = mock_get(
+            "/expiry/secrets",
+            json!({
+                "value": [{
+                    "id": "https://test-keyvault.vault.azure.net/secrets/db-password",
+                    "attributes": { "enabled": true, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                }],
+                "nextLink": null
+            }),
+        );
+        let _m2 = mock_get(
+            "/expiry/secrets/db-password/versions",
+            json!({ "value": versions, "nextLink": null }),
+        );
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/expiry", mockito::server_url());
+
+        let report = client.scan_expiring(Duration::days(30)).await.unwrap();
+        let reported = report
+            .secrets()
+            .iter()
+            .map(|s| (s.version().as_str(), *s.status()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("VERSION_1", ExpiryStatus::Expired),
+                ("VERSION_2", ExpiryStatus::ExpiringSoon),
+                ("VERSION_4", ExpiryStatus::NoExpiry),
+            ],
+            reported
+        );
+        assert!(report.secrets().iter().all(|s| s.owner() == &Some("team-a".to_owned())));
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            Some("secret_name,version,status,time_expires,owner,content_type"),
+            lines.next()
+        );
+        assert!(lines.next().unwrap().starts_with("db-password,VERSION_1,expired,"));
+        assert_eq!(2, lines.count());
+    }
+}
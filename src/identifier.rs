@@ -0,0 +1,157 @@
+use crate::KeyVaultError;
+use getset::{CopyGetters, Getters};
+use reqwest::Url;
+use std::fmt;
+use std::str::FromStr;
+
+/// The collection within a Key Vault which an identifier points into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyVaultCollection {
+    Secrets,
+    Keys,
+    Certificates,
+    DeletedSecrets,
+}
+
+impl KeyVaultCollection {
+    fn from_path_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "secrets" => Some(KeyVaultCollection::Secrets),
+            "keys" => Some(KeyVaultCollection::Keys),
+            "certificates" => Some(KeyVaultCollection::Certificates),
+            "deletedsecrets" => Some(KeyVaultCollection::DeletedSecrets),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeyVaultCollection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyVaultCollection::Secrets => write!(f, "secrets"),
+            KeyVaultCollection::Keys => write!(f, "keys"),
+            KeyVaultCollection::Certificates => write!(f, "certificates"),
+            KeyVaultCollection::DeletedSecrets => write!(f, "deletedsecrets"),
+        }
+    }
+}
+
+/// A parsed Key Vault object identifier, of the form
+/// `https://{vault}.{endpoint suffix}/{collection}/{name}[/{version}]`.
+///
+/// # Example
+///
+/// ```
+/// use azure_sdk_keyvault::KeyVaultIdentifier;
+/// use azure_sdk_keyvault::identifier::KeyVaultCollection;
+///
+/// let id: KeyVaultIdentifier = "https://test-keyvault.vault.azure.net/secrets/test-secret/4387e9f3d6e14c459867679a90fd0f79"
+///     .parse()
+///     .unwrap();
+/// assert_eq!("https://test-keyvault.vault.azure.net", id.vault_url());
+/// assert_eq!(KeyVaultCollection::Secrets, id.collection());
+/// assert_eq!("test-secret", id.name());
+/// assert_eq!(Some("4387e9f3d6e14c459867679a90fd0f79"), id.version().as_deref());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters, CopyGetters)]
+pub struct KeyVaultIdentifier {
+    #[getset(get = "pub")]
+    vault_url: String,
+    #[getset(get_copy = "pub")]
+    collection: KeyVaultCollection,
+    #[getset(get = "pub")]
+    name: String,
+    #[getset(get = "pub")]
+    version: Option<String>,
+}
+
+impl KeyVaultIdentifier {
+    /// Parses a Key Vault object identifier, such as the `id` of a secret.
+    pub fn parse(identifier: &str) -> Result<Self, KeyVaultError> {
+        let invalid = |reason: &str| KeyVaultError::InvalidIdentifier(format!("'{}' {}", identifier, reason));
+
+        let url = Url::parse(identifier).map_err(|_| invalid("is not a valid URL"))?;
+        if url.scheme() != "https" && url.scheme() != "http" {
+            return Err(invalid("is not an HTTP(S) URL"));
+        }
+        if url.host_str().is_none() {
+            return Err(invalid("has no host"));
+        }
+
+        let mut segments = url.path_segments().unwrap().collect::<Vec<_>>();
+        if segments.last() == Some(&"") {
+            segments.pop();
+        }
+        let (collection, name, version) = match segments.as_slice() {
+            [collection, name] => (collection, name, None),
+            [collection, name, version] => (collection, name, Some((*version).to_owned())),
+            _ => {
+                return Err(invalid(
+                    "does not have the form {vault}/{collection}/{name}[/{version}]",
+                ))
+            }
+        };
+        let collection = KeyVaultCollection::from_path_segment(collection)
+            .ok_or_else(|| invalid("does not point into a known Key Vault collection"))?;
+        if name.is_empty() {
+            return Err(invalid("has an empty name"));
+        }
+
+        Ok(Self {
+            vault_url: url.origin().ascii_serialization(),
+            collection,
+            name: (*name).to_owned(),
+            version,
+        })
+    }
+
+    /// Whether this identifier points into the vault at the given endpoint.
+    pub(crate) fn is_in_vault(&self, keyvault_endpoint: &str) -> bool {
+        matches!(Url::parse(keyvault_endpoint), Ok(url) if url.origin().ascii_serialization() == self.vault_url)
+    }
+}
+
+impl FromStr for KeyVaultIdentifier {
+    type Err = KeyVaultError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyVaultIdentifier::parse(s)
+    }
+}
+
+impl fmt::Display for KeyVaultIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.vault_url, self.collection, self.name)?;
+        if let Some(version) = &self.version {
+            write!(f, "/{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_identifiers() {
+        let id = KeyVaultIdentifier::parse("https://test-keyvault.vault.azure.net:443/secrets/test-secret").unwrap();
+        assert_eq!("https://test-keyvault.vault.azure.net", id.vault_url());
+        assert_eq!(KeyVaultCollection::Secrets, id.collection());
+        assert_eq!("test-secret", id.name());
+        assert_eq!(None, *id.version());
+        assert_eq!(
+            "https://test-keyvault.vault.azure.net/secrets/test-secret",
+            id.to_string()
+        );
+
+        let id = KeyVaultIdentifier::parse("https://test-keyvault.vault.azure.cn/deletedsecrets/test-secret/").unwrap();
+        assert_eq!("https://test-keyvault.vault.azure.cn", id.vault_url());
+        assert_eq!(KeyVaultCollection::DeletedSecrets, id.collection());
+
+        assert!(KeyVaultIdentifier::parse("https://test-keyvault.vault.azure.net/secrets").is_err());
+        assert!(KeyVaultIdentifier::parse("https://test-keyvault.vault.azure.net/storage/test-secret").is_err());
+        assert!(KeyVaultIdentifier::parse("https://test-keyvault.vault.azure.net/secrets/a/b/c").is_err());
+        assert!(KeyVaultIdentifier::parse("test-secret").is_err());
+    }
+}
@@ -1,7 +1,36 @@
+#[cfg(test)]
+#[macro_use]
+mod test_utils;
+
+mod archive_encryption;
+pub mod backup;
+pub mod cache;
+mod chunked;
 mod client;
+pub mod diff;
+mod encoding;
+pub mod events;
+pub mod expiry;
+pub mod identifier;
+pub mod lint;
+pub mod migration;
+pub mod paging;
+pub mod poller;
+pub mod pruning;
+pub mod reference;
+pub mod report;
+pub mod rotation;
 pub mod secret;
+mod secret_value;
+pub mod template;
+pub mod validation;
+pub mod watch;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 pub use client::KeyVaultClient;
+pub use identifier::KeyVaultIdentifier;
 pub use secret::RecoveryLevel;
+pub use secret_value::SecretValue;
 
 use thiserror::Error;
 
@@ -13,6 +42,80 @@ pub enum KeyVaultError {
     #[error("Azure Active Directory authorization error")]
     AuthorizationError(#[from] anyhow::Error),
 
+    #[error("Timed out after {timeout:?} waiting for the {operation} of secret '{secret_name}' to complete")]
+    OperationTimedOut {
+        operation: String,
+        secret_name: String,
+        timeout: std::time::Duration,
+    },
+
+    #[error("Invalid Key Vault identifier: {0}")]
+    InvalidIdentifier(String),
+
+    #[error("Secret '{secret_name}' could not be deserialized ({category} error at line {line}, column {column})")]
+    SecretDeserializationError {
+        secret_name: String,
+        category: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("Secret '{secret_name}' has content type {actual:?}, expected '{expected}'")]
+    ContentTypeMismatch {
+        secret_name: String,
+        expected: String,
+        actual: Option<String>,
+    },
+
+    #[error("Secret '{secret_name}' is not validly encoded for content type '{content_type}'")]
+    SecretDecodingError { secret_name: String, content_type: String },
+
+    #[error("Chunked secret '{secret_name}' is inconsistent: {reason}")]
+    ChunkedSecretCorrupted { secret_name: String, reason: String },
+
+    #[error("I/O error")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid backup archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Secret '{secret_name}' is invalid: {reason}")]
+    InvalidSecret {
+        secret_name: String,
+        reason: validation::SecretValidationError,
+    },
+
+    #[error("Secrets already exist in the Key Vault: {secret_names:?}")]
+    RestoreConflict { secret_names: Vec<String> },
+
+    #[error("{} template placeholders could not be resolved: {placeholders:?}", placeholders.len())]
+    UnresolvedPlaceholders {
+        placeholders: Vec<template::UnresolvedPlaceholder>,
+    },
+
+    #[error("Invalid Key Vault event: {0}")]
+    InvalidEvent(String),
+
+    #[error("Not found in the Key Vault: {0}")]
+    NotFound(String),
+
+    #[error("Disabled in the Key Vault: {0}")]
+    SecretDisabled(String),
+
     #[error("General error: {0}")]
     GeneralError(String),
 }
+
+/// Hex-encoded SHA-256 digest of a value.
+pub(crate) fn sha256_hex(value: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(value))
+}
+
+/// Random bytes from the operating system, for salts and nonces.
+pub(crate) fn random_bytes(length: usize) -> Vec<u8> {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    let mut bytes = vec![0u8; length];
+    chacha20poly1305::aead::OsRng.fill_bytes(&mut bytes);
+    bytes
+}
@@ -0,0 +1,349 @@
+use crate::secret::KeyVaultSecretBaseIdentifier;
+use crate::{KeyVaultClient, KeyVaultError};
+use chrono::{DateTime, Duration, Utc};
+use getset::Getters;
+use regex::Regex;
+use serde::Serialize;
+
+/// How serious a [`LintFinding`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A hygiene rule checked against every secret by a [`LintPolicy`].
+#[derive(Debug, Clone)]
+pub enum LintRule {
+    /// The current version must have an expiry date.
+    MustHaveExpiry,
+    /// The current version must expire within this duration of becoming valid.
+    /// Versions without expiry are left to [`MustHaveExpiry`](LintRule::MustHaveExpiry).
+    MaxLifetime(Duration),
+    /// The current version must carry each of these tags.
+    RequiredTags(Vec<String>),
+    /// The current version must have one of these content types.
+    AllowedContentTypes(Vec<String>),
+    /// A secret must not have more enabled versions than this.
+    MaxEnabledVersions(usize),
+    /// The secret name must match this pattern.
+    NamingPattern(Regex),
+    /// A secret must not stay disabled for longer than this without being deleted.
+    DisabledNotDeleted(Duration),
+}
+
+impl LintRule {
+    /// The name under which findings of this rule are reported.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintRule::MustHaveExpiry => "must-have-expiry",
+            LintRule::MaxLifetime(_) => "max-lifetime",
+            LintRule::RequiredTags(_) => "required-tags",
+            LintRule::AllowedContentTypes(_) => "allowed-content-types",
+            LintRule::MaxEnabledVersions(_) => "max-enabled-versions",
+            LintRule::NamingPattern(_) => "naming-pattern",
+            LintRule::DisabledNotDeleted(_) => "disabled-not-deleted",
+        }
+    }
+
+    fn check(
+        &self,
+        secret: &KeyVaultSecretBaseIdentifier,
+        versions: &[KeyVaultSecretBaseIdentifier],
+        now: DateTime<Utc>,
+    ) -> Option<String> {
+        match self {
+            LintRule::MustHaveExpiry => match secret.time_expires() {
+                None => Some("the current version never expires".to_owned()),
+                Some(_) => None,
+            },
+            LintRule::MaxLifetime(max_lifetime) => {
+                let expires = (*secret.time_expires())?;
+                let valid_from = secret.time_not_before().unwrap_or(*secret.time_created());
+                let lifetime = expires - valid_from;
+                if lifetime > *max_lifetime {
+                    Some(format!(
+                        "the current version is valid for {} days, more than {} days",
+                        lifetime.num_days(),
+                        max_lifetime.num_days()
+                    ))
+                } else {
+                    None
+                }
+            }
+            LintRule::RequiredTags(tags) => {
+                let missing = tags
+                    .iter()
+                    .filter(|tag| !secret.tags().contains_key(*tag))
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                if missing.is_empty() {
+                    None
+                } else {
+                    Some(format!("missing tags: {}", missing.join(", ")))
+                }
+            }
+            LintRule::AllowedContentTypes(content_types) => match secret.content_type() {
+                Some(content_type) if content_types.contains(content_type) => None,
+                Some(content_type) => Some(format!("content type {} is not allowed", content_type)),
+                None => Some("the current version has no content type".to_owned()),
+            },
+            LintRule::MaxEnabledVersions(max_enabled) => {
+                let enabled = versions.iter().filter(|v| *v.enabled()).count();
+                if enabled > *max_enabled {
+                    Some(format!("{} versions are enabled, more than {}", enabled, max_enabled))
+                } else {
+                    None
+                }
+            }
+            LintRule::NamingPattern(pattern) => {
+                if pattern.is_match(secret.name()) {
+                    None
+                } else {
+                    Some(format!("the name does not match {}", pattern))
+                }
+            }
+            LintRule::DisabledNotDeleted(max_disabled) => {
+                // Disabling a secret updates it, so its update time is when it was disabled at the latest.
+                let disabled_for = now - *secret.time_updated();
+                if !*secret.enabled() && disabled_for > *max_disabled {
+                    Some(format!(
+                        "disabled for {} days without being deleted",
+                        disabled_for.num_days()
+                    ))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// A rule violated by a secret.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct LintFinding {
+    secret_name: String,
+    rule: &'static str,
+    severity: Severity,
+    message: String,
+}
+
+/// The rules checked by [`lint_vault`](KeyVaultClient::lint_vault), each with the severity of its findings.
+#[derive(Debug, Clone, Default)]
+pub struct LintPolicy {
+    rules: Vec<(LintRule, Severity)>,
+}
+
+impl LintPolicy {
+    /// Adds a rule, reported with the given severity.
+    pub fn with_rule(mut self, rule: LintRule, severity: Severity) -> Self {
+        self.rules.push((rule, severity));
+        self
+    }
+
+    /// Whether any rule looks at the versions of a secret, which then have to be listed.
+    pub fn needs_versions(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|(rule, _)| matches!(rule, LintRule::MaxEnabledVersions(_)))
+    }
+
+    /// Checks a secret, as returned by [`list_secrets`](KeyVaultClient::list_secrets), against every rule.
+    /// `versions` are the versions of the secret, as returned by
+    /// [`get_secret_versions`](KeyVaultClient::get_secret_versions); they can be left empty
+    /// unless [`needs_versions`](LintPolicy::needs_versions) is true.
+    pub fn evaluate(
+        &self,
+        secret: &KeyVaultSecretBaseIdentifier,
+        versions: &[KeyVaultSecretBaseIdentifier],
+        now: DateTime<Utc>,
+    ) -> Vec<LintFinding> {
+        self.rules
+            .iter()
+            .filter_map(|(rule, severity)| {
+                rule.check(secret, versions, now).map(|message| LintFinding {
+                    secret_name: secret.name().clone(),
+                    rule: rule.name(),
+                    severity: *severity,
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The findings of [`lint_vault`](KeyVaultClient::lint_vault).
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct LintReport {
+    vault_url: String,
+    time_linted: DateTime<Utc>,
+    /// Findings, most severe first.
+    findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// Whether any finding has at least the given severity.
+    pub fn has_findings(&self, severity: Severity) -> bool {
+        self.findings.iter().any(|f| f.severity >= severity)
+    }
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Checks every secret in the Key Vault against the rules of a policy.
+    ///
+    /// This operation requires the secrets/list permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::lint::{LintPolicy, LintRule, Severity};
+    /// use chrono::Duration;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let policy = LintPolicy::default()
+    ///         .with_rule(LintRule::MustHaveExpiry, Severity::Error)
+    ///         .with_rule(LintRule::MaxLifetime(Duration::days(365)), Severity::Warning)
+    ///         .with_rule(
+    ///             LintRule::RequiredTags(vec!["owner".to_owned(), "env".to_owned()]),
+    ///             Severity::Warning,
+    ///         );
+    ///     let report = client.lint_vault(&policy).await.unwrap();
+    ///     println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    ///     assert!(!report.has_findings(Severity::Error));
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn lint_vault(&mut self, policy: &LintPolicy) -> Result<LintReport, KeyVaultError> {
+        let time_linted = Utc::now();
+        let mut findings = Vec::new();
+        for secret in self.list_secrets().await? {
+            let versions = if policy.needs_versions() {
+                self.get_secret_versions(secret.name()).await?
+            } else {
+                Vec::new()
+            };
+            findings.extend(policy.evaluate(&secret, &versions, time_linted));
+        }
+        findings.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.secret_name.cmp(&b.secret_name))
+        });
+
+        Ok(LintReport {
+            vault_url: self.keyvault_endpoint.clone(),
+            time_linted,
+            findings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::mock_get;
+
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn lint_vault() {
+        let _m1 = mock_get(
+            "/lint/secrets",
+            json!({
+                "value": [
+                    {
+                        "id": "https://test-keyvault.vault.azure.net/secrets/app-password",
+                        "contentType": "text/plain",
+                        "tags": { "owner": "team-a", "env": "prod" },
+                        "attributes": {
+                            "enabled": true,
+                            "exp": 1_600_000_000 + 30 * 86_400,
+                            "created": 1_600_000_000,
+                            "updated": 1_600_000_000
+                        }
+                    },
+                    {
+                        "id": "https://test-keyvault.vault.azure.net/secrets/LegacyPassword",
+                        "contentType": "application/x-pkcs12",
+                        "tags": { "owner": "team-b" },
+                        "attributes": {
+                            "enabled": false,
+                            "exp": 1_600_000_000 + 3 * 365 * 86_400,
+                            "created": 1_600_000_000,
+                            "updated": 1_600_000_000
+                        }
+                    }
+                ],
+                "nextLink": null
+            }),
+        );
+        let versions = |secret_name: &str, enabled: &[bool]| {
+            let versions = enabled
+                .iter()
+                .enumerate()
+                .map(|(i, enabled)| {
+                    json!({
+                        "id": format!("https://test-keyvault.vault.azure.net/secrets/{}/VERSION_{}", secret_name, i),
+                        "attributes": { "enabled": enabled, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                    })
+                })
+                .collect::<Vec<_>>();
+            mock_get(
+                &format!("/lint/secrets/{}/versions", secret_name),
+                json!({ "value": versions, "nextLink": null }),
+            )
+        };
+        let _m2 = versions("app-password", &[true, true]);
+        let _m3 = versions("LegacyPassword", &[true, true, false, true]);
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/lint", mockito::server_url());
+
+        let policy = LintPolicy::default()
+            .with_rule(LintRule::MustHaveExpiry, Severity::Error)
+            .with_rule(LintRule::MaxLifetime(Duration::days(365)), Severity::Warning)
+            .with_rule(
+                LintRule::RequiredTags(vec!["owner".to_owned(), "env".to_owned()]),
+                Severity::Warning,
+            )
+            .with_rule(
+                LintRule::AllowedContentTypes(vec!["text/plain".to_owned()]),
+                Severity::Info,
+            )
+            .with_rule(LintRule::MaxEnabledVersions(2), Severity::Info)
+            .with_rule(
+                LintRule::NamingPattern(Regex::new("^[a-z0-9-]+$").unwrap()),
+                Severity::Warning,
+            )
+            .with_rule(LintRule::DisabledNotDeleted(Duration::days(90)), Severity::Error);
+        let report = client.lint_vault(&policy).await.unwrap();
+
+        assert!(report.findings().iter().all(|f| f.secret_name() == "LegacyPassword"));
+        let rules = report.findings().iter().map(|f| *f.rule()).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "disabled-not-deleted",
+                "max-lifetime",
+                "required-tags",
+                "naming-pattern",
+                "allowed-content-types",
+                "max-enabled-versions",
+            ],
+            rules
+        );
+        assert!(report.has_findings(Severity::Error));
+    }
+}
@@ -0,0 +1,499 @@
+use crate::report::{describe_error, SecretOutcomeStatus, VaultOperationReport, VaultProgress};
+use crate::secret::{KeyVaultSecret, KeyVaultSecretBaseIdentifier, SecretProperties};
+use crate::{KeyVaultClient, KeyVaultError};
+use getset::{CopyGetters, Getters};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A property of a secret which can differ between two vaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretProperty {
+    Value,
+    ContentType,
+    Tags,
+    Enabled,
+    NotBefore,
+    Expires,
+}
+
+/// The properties which differ between two versions of a secret.
+pub(crate) fn differing_properties(first: &KeyVaultSecret, second: &KeyVaultSecret) -> Vec<SecretProperty> {
+    let mut properties = Vec::new();
+    if first.value().expose() != second.value().expose() {
+        properties.push(SecretProperty::Value);
+    }
+    if first.content_type() != second.content_type() {
+        properties.push(SecretProperty::ContentType);
+    }
+    if first.tags() != second.tags() {
+        properties.push(SecretProperty::Tags);
+    }
+    if first.enabled() != second.enabled() {
+        properties.push(SecretProperty::Enabled);
+    }
+    if first.time_not_before() != second.time_not_before() {
+        properties.push(SecretProperty::NotBefore);
+    }
+    if first.time_expires() != second.time_expires() {
+        properties.push(SecretProperty::Expires);
+    }
+    properties
+}
+
+/// The state of a secret in the destination vault, before it was copied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DestinationState {
+    /// The secret does not exist in the destination vault. All its enabled versions are copied.
+    Missing,
+    /// The latest version of the secret is the same in both vaults. Nothing is copied.
+    Identical,
+    /// The latest version of the secret differs between the vaults. Only the latest version is copied.
+    Different { properties: Vec<SecretProperty> },
+}
+
+/// A secret copied, or to be copied in a dry run, from one vault to another.
+#[derive(Debug, Clone, Getters, CopyGetters, Serialize)]
+pub struct SecretCopy {
+    #[getset(get = "pub")]
+    source_name: String,
+    #[getset(get = "pub")]
+    destination_name: String,
+    #[getset(get = "pub")]
+    destination_state: DestinationState,
+    /// The number of versions copied.
+    #[getset(get_copy = "pub")]
+    versions: usize,
+    /// Whether every version to copy was copied. A replay which fails partway leaves the destination
+    /// secret with only its oldest versions, and is reported as failed.
+    #[getset(get_copy = "pub")]
+    complete: bool,
+}
+
+/// The result of [`copy_secrets`](copy_secrets): what was found in the destination vault for each secret,
+/// and what happened to it.
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct CopyReport {
+    copies: Vec<SecretCopy>,
+    outcomes: VaultOperationReport,
+}
+
+type SecretFilter<'o> = Box<dyn Fn(&KeyVaultSecretBaseIdentifier) -> bool + Send + 'o>;
+type ProgressCallback<'o> = Box<dyn FnMut(&VaultProgress) + Send + 'o>;
+
+/// Options for [`copy_secrets`](copy_secrets).
+#[derive(Default)]
+pub struct CopyOptions<'o> {
+    filter: Option<SecretFilter<'o>>,
+    rename_rules: Vec<(Regex, String)>,
+    dry_run: bool,
+    progress: Option<ProgressCallback<'o>>,
+}
+
+impl<'o> CopyOptions<'o> {
+    /// Only copies the secrets of the source vault for which the filter returns `true`.
+    pub fn with_filter(mut self, filter: impl Fn(&KeyVaultSecretBaseIdentifier) -> bool + Send + 'o) -> Self {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Renames the secrets whose name matches `pattern` in the destination vault, replacing the match with
+    /// `replacement` (which may refer to capture groups, such as `$1`). Only the first matching rule applies.
+    pub fn with_rename_rule(mut self, pattern: Regex, replacement: impl Into<String>) -> Self {
+        self.rename_rules.push((pattern, replacement.into()));
+        self
+    }
+
+    /// Compares the vaults and reports what would be copied, without writing to the destination vault.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets a callback which is invoked after each secret has been processed.
+    pub fn with_progress(mut self, progress: impl FnMut(&VaultProgress) + Send + 'o) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    fn destination_name(&self, secret_name: &str) -> String {
+        self.rename_rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(secret_name))
+            .map(|(pattern, replacement)| pattern.replace(secret_name, replacement.as_str()).into_owned())
+            .unwrap_or_else(|| secret_name.to_owned())
+    }
+}
+
+/// Copies secrets from one Key Vault to another, by replaying their versions.
+///
+/// Unlike [`backup_secret`](KeyVaultClient::backup_secret) and [`restore_secret`](KeyVaultClient::restore_secret),
+/// this works across subscriptions and geographies. Secrets missing from the destination vault get every
+/// enabled version replayed oldest-first, preserving their content type, tags, activation and expiry.
+/// Secrets which differ only get their latest version copied, and identical secrets are left alone.
+/// Disabled versions are not copied, as their values can not be read.
+///
+/// This operation requires the secrets/list and secrets/get permissions on the source vault,
+/// and the secrets/list, secrets/get and secrets/set permissions on the destination vault.
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::migration::{copy_secrets, CopyOptions};
+/// use regex::Regex;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let mut staging = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"STAGING_KEYVAULT_NAME",
+///     );
+///     let mut production = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"PRODUCTION_KEYVAULT_NAME",
+///     );
+///     let options = CopyOptions::default()
+///         .with_filter(|s| s.name().starts_with("app-"))
+///         .with_rename_rule(Regex::new("^app-").unwrap(), "prod-app-")
+///         .with_dry_run(true);
+///     let report = copy_secrets(&mut staging, &mut production, options).await.unwrap();
+///     println!("{}", serde_json::to_string_pretty(&report).unwrap());
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+pub async fn copy_secrets(
+    source: &mut KeyVaultClient<'_>,
+    destination: &mut KeyVaultClient<'_>,
+    mut options: CopyOptions<'_>,
+) -> Result<CopyReport, KeyVaultError> {
+    let destination_names = destination
+        .list_secrets()
+        .await?
+        .into_iter()
+        .map(|s| s.name().clone())
+        .collect::<HashSet<_>>();
+    let secrets = source
+        .list_secrets()
+        .await?
+        .into_iter()
+        .filter(|s| match &options.filter {
+            Some(filter) => filter(s),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    let mut report = CopyReport::default();
+    for (index, secret) in secrets.iter().enumerate() {
+        let destination_name = options.destination_name(secret.name());
+        let exists = destination_names.contains(&destination_name);
+        let status = match copy_secret(
+            source,
+            destination,
+            secret.name(),
+            &destination_name,
+            exists,
+            options.dry_run,
+        )
+        .await
+        {
+            Ok(None) => SecretOutcomeStatus::Skipped {
+                reason: "the secret has no enabled versions".to_owned(),
+            },
+            Ok(Some((copy, Some(e)))) => {
+                let status = SecretOutcomeStatus::Failed {
+                    error: format!(
+                        "only {} versions were copied, the history in the destination vault is incomplete: {}",
+                        copy.versions,
+                        describe_error(&e)
+                    ),
+                };
+                report.copies.push(copy);
+                status
+            }
+            Ok(Some((copy, None))) => {
+                let status = if copy.destination_state == DestinationState::Identical {
+                    SecretOutcomeStatus::Skipped {
+                        reason: "identical in the destination vault".to_owned(),
+                    }
+                } else if options.dry_run {
+                    SecretOutcomeStatus::Skipped {
+                        reason: "dry run".to_owned(),
+                    }
+                } else {
+                    SecretOutcomeStatus::Succeeded
+                };
+                report.copies.push(copy);
+                status
+            }
+            Err(e) => SecretOutcomeStatus::Failed {
+                error: describe_error(&e),
+            },
+        };
+        report.outcomes.record(secret.name(), status);
+        if let Some(progress) = &mut options.progress {
+            progress(&VaultProgress::new(secret.name(), index + 1, secrets.len()));
+        }
+    }
+    Ok(report)
+}
+
+/// Copies a secret, returning the error which interrupted the replay of its versions, if any.
+async fn copy_secret(
+    source: &mut KeyVaultClient<'_>,
+    destination: &mut KeyVaultClient<'_>,
+    source_name: &str,
+    destination_name: &str,
+    exists: bool,
+    dry_run: bool,
+) -> Result<Option<(SecretCopy, Option<KeyVaultError>)>, KeyVaultError> {
+    let mut versions = source.get_secret_versions(source_name).await?;
+    // Disabling or tagging a version changes its update time, but never its creation time.
+    versions.sort_by(|a, b| a.time_created().cmp(b.time_created()));
+    let enabled_versions = versions
+        .into_iter()
+        .filter(|v| *v.enabled())
+        .filter_map(|v| v.version().clone())
+        .collect::<Vec<_>>();
+    let latest_version = match enabled_versions.last() {
+        Some(latest_version) => latest_version,
+        None => return Ok(None),
+    };
+    let latest = source.get_secret_with_version(source_name, latest_version).await?;
+
+    let (destination_state, versions_to_copy) = if exists {
+        let current = destination.get_secret(destination_name).await?;
+        let properties = differing_properties(&latest, &current);
+        if properties.is_empty() {
+            (DestinationState::Identical, &enabled_versions[..0])
+        } else {
+            (
+                DestinationState::Different { properties },
+                std::slice::from_ref(latest_version),
+            )
+        }
+    } else {
+        (DestinationState::Missing, &enabled_versions[..])
+    };
+
+    let mut copied = versions_to_copy.len();
+    let mut error = None;
+    if !dry_run {
+        for (index, version) in versions_to_copy.iter().enumerate() {
+            if let Err(e) = copy_version(
+                source,
+                destination,
+                source_name,
+                destination_name,
+                version,
+                (version == latest_version).then_some(&latest),
+            )
+            .await
+            {
+                copied = index;
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    let copy = SecretCopy {
+        source_name: source_name.to_owned(),
+        destination_name: destination_name.to_owned(),
+        destination_state,
+        versions: copied,
+        complete: error.is_none(),
+    };
+    Ok(Some((copy, error)))
+}
+
+async fn copy_version(
+    source: &mut KeyVaultClient<'_>,
+    destination: &mut KeyVaultClient<'_>,
+    source_name: &str,
+    destination_name: &str,
+    version: &str,
+    latest: Option<&KeyVaultSecret>,
+) -> Result<(), KeyVaultError> {
+    let fetched;
+    let secret = match latest {
+        Some(latest) => latest,
+        None => {
+            fetched = source.get_secret_with_version(source_name, version).await?;
+            &fetched
+        }
+    };
+    destination
+        .set_secret_value_with_properties(destination_name, secret.value(), &SecretProperties::from(secret))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::test_utils::{mock_get, secret_bundle};
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    fn secret_version(version: &str, enabled: bool, updated: i64) -> serde_json::Value {
+        json!({
+            "id": format!("https://source-keyvault.vault.azure.net/secrets/app-password/{}", version),
+            "attributes": { "enabled": enabled, "created": updated, "updated": updated }
+        })
+    }
+
+    fn app_password_bundle(version: &str, value: &str) -> serde_json::Value {
+        let mut bundle = secret_bundle("app-password", version, value);
+        bundle["tags"] = json!({ "owner": "team-a" });
+        bundle["attributes"]["exp"] = json!(1_700_000_000);
+        bundle
+    }
+
+    #[tokio::test]
+    async fn copy_secrets_replays_enabled_versions() {
+        let _m1 = mock_get(
+            "/source/secrets",
+            json!({
+                "value": [
+                    secret_version("", true, 1_600_000_000),
+                    {
+                        "id": "https://source-keyvault.vault.azure.net/secrets/unrelated-secret",
+                        "attributes": { "enabled": true, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                    }
+                ],
+                "nextLink": null
+            }),
+        );
+        let _m2 = mock_get("/destination/secrets", json!({ "value": [], "nextLink": null }));
+        let _m3 = mock_get(
+            "/source/secrets/app-password/versions",
+            json!({
+                "value": [
+                    secret_version("VERSION_1", true, 1_600_000_001),
+                    secret_version("VERSION_2", false, 1_600_000_002),
+                    secret_version("VERSION_3", true, 1_600_000_003)
+                ],
+                "nextLink": null
+            }),
+        );
+        let _m4 = mock_get(
+            "/source/secrets/app-password/VERSION_1",
+            app_password_bundle("VERSION_1", "old"),
+        );
+        let _m5 = mock_get(
+            "/source/secrets/app-password/VERSION_3",
+            app_password_bundle("VERSION_3", "new"),
+        );
+        let puts = ["old", "new"]
+            .iter()
+            .map(|value| {
+                mock("PUT", "/destination/secrets/prod-app-password")
+                    .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+                    .match_body(Matcher::Json(json!({
+                        "value": value,
+                        "tags": { "owner": "team-a" },
+                        "attributes": { "enabled": true, "exp": 1_700_000_000 }
+                    })))
+                    .with_body(app_password_bundle("COPIED", value).to_string())
+                    .with_status(200)
+                    .expect(1)
+                    .create()
+            })
+            .collect::<Vec<_>>();
+
+        let mut source = mock_client!(&"source-keyvault");
+        source.keyvault_endpoint = format!("{}/source", mockito::server_url());
+        let mut destination = mock_client!(&"destination-keyvault");
+        destination.keyvault_endpoint = format!("{}/destination", mockito::server_url());
+        let options = || {
+            CopyOptions::default()
+                .with_filter(|s| s.name().starts_with("app-"))
+                .with_rename_rule(Regex::new("^app-").unwrap(), "prod-app-")
+        };
+
+        let report = copy_secrets(&mut source, &mut destination, options().with_dry_run(true))
+            .await
+            .unwrap();
+        assert_eq!(1, report.copies().len());
+        assert_eq!(1, report.outcomes().skipped().count());
+
+        let report = copy_secrets(&mut source, &mut destination, options()).await.unwrap();
+        let copy = &report.copies()[0];
+        assert_eq!("prod-app-password", copy.destination_name());
+        assert_eq!(&DestinationState::Missing, copy.destination_state());
+        assert_eq!(2, copy.versions());
+        assert!(report.outcomes().is_success());
+        for put in puts {
+            put.assert();
+        }
+    }
+
+    #[tokio::test]
+    async fn copy_secrets_reports_incomplete_replays() {
+        let _m1 = mock_get(
+            "/partial-source/secrets",
+            json!({ "value": [secret_version("", true, 1_600_000_000)], "nextLink": null }),
+        );
+        let _m2 = mock_get("/partial-destination/secrets", json!({ "value": [], "nextLink": null }));
+        // The oldest version was updated last, so it is listed first.
+        let _m3 = mock_get(
+            "/partial-source/secrets/app-password/versions",
+            json!({
+                "value": [
+                    {
+                        "id": "https://source-keyvault.vault.azure.net/secrets/app-password/VERSION_1",
+                        "attributes": { "enabled": true, "created": 1_600_000_001, "updated": 1_600_000_009 }
+                    },
+                    secret_version("VERSION_2", true, 1_600_000_002)
+                ],
+                "nextLink": null
+            }),
+        );
+        let _m4 = mock_get(
+            "/partial-source/secrets/app-password/VERSION_1",
+            app_password_bundle("VERSION_1", "old"),
+        );
+        let _m5 = mock_get(
+            "/partial-source/secrets/app-password/VERSION_2",
+            app_password_bundle("VERSION_2", "new"),
+        );
+        let old = mock("PUT", "/partial-destination/secrets/app-password")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::PartialJson(json!({ "value": "old" })))
+            .with_body(app_password_bundle("COPIED", "old").to_string())
+            .with_status(200)
+            .expect(1)
+            .create();
+        let _new = mock("PUT", "/partial-destination/secrets/app-password")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::PartialJson(json!({ "value": "new" })))
+            .with_body(json!({ "error": { "code": "Throttled", "message": "Too many requests" } }).to_string())
+            .with_status(429)
+            .create();
+
+        let mut source = mock_client!(&"source-keyvault");
+        source.keyvault_endpoint = format!("{}/partial-source", mockito::server_url());
+        let mut destination = mock_client!(&"destination-keyvault");
+        destination.keyvault_endpoint = format!("{}/partial-destination", mockito::server_url());
+
+        let report = copy_secrets(&mut source, &mut destination, CopyOptions::default())
+            .await
+            .unwrap();
+        let copy = &report.copies()[0];
+        assert_eq!(1, copy.versions());
+        assert!(!copy.complete());
+        assert_eq!(1, report.outcomes().failed().count());
+        old.assert();
+    }
+}
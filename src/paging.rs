@@ -0,0 +1,56 @@
+use getset::Getters;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Opaque token pointing at the next page of a listing.
+///
+/// Tokens can be persisted (they serialize as a plain string) and handed back to the matching
+/// `*_page` method later on, to resume a listing where it left off.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ContinuationToken(String);
+
+impl ContinuationToken {
+    /// Restores a token previously obtained through [`as_str`](ContinuationToken::as_str) or `Display`.
+    pub fn new(token: String) -> Self {
+        Self(token)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ContinuationToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A single page of a Key Vault listing.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct KeyVaultPage<T> {
+    items: Vec<T>,
+    /// Token for the next page, or `None` if this is the last one.
+    continuation_token: Option<ContinuationToken>,
+}
+
+impl<T> KeyVaultPage<T> {
+    pub(crate) fn new(items: Vec<T>, next_link: Option<String>) -> Self {
+        Self {
+            items,
+            continuation_token: next_link.map(ContinuationToken),
+        }
+    }
+
+    /// Consumes the page, returning its items.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Consumes the page, returning its items and the token for the next page.
+    pub fn into_parts(self) -> (Vec<T>, Option<ContinuationToken>) {
+        (self.items, self.continuation_token)
+    }
+}
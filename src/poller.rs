@@ -0,0 +1,287 @@
+use crate::client::API_VERSION;
+use crate::{KeyVaultClient, KeyVaultError};
+use getset::{CopyGetters, Getters};
+use reqwest::Url;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+pub(crate) const DEFAULT_POLLING_INTERVAL: Duration = Duration::from_secs(2);
+pub(crate) const DEFAULT_POLLING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// A long-running secret operation which completes asynchronously on the service side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretOperation {
+    /// The secret is being deleted, into the deleted state in vaults with soft-delete enabled.
+    Delete,
+    /// A deleted secret is being recovered back into the vault.
+    Recover,
+    /// A deleted secret is being permanently removed from the vault.
+    Purge,
+}
+
+impl fmt::Display for SecretOperation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretOperation::Delete => write!(f, "deletion"),
+            SecretOperation::Recover => write!(f, "recovery"),
+            SecretOperation::Purge => write!(f, "purge"),
+        }
+    }
+}
+
+/// Polls the Key Vault until a deletion, recovery or purge of a secret has completed.
+///
+/// Returned by [`delete_secret`](KeyVaultClient::delete_secret),
+/// [`recover_deleted_secret`](KeyVaultClient::recover_deleted_secret) and
+/// [`purge_deleted_secret`](KeyVaultClient::purge_deleted_secret).
+/// The operation proceeds on the service side whether or not the poller is awaited.
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use std::time::Duration;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let mut client = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"KEYVAULT_NAME",
+///     );
+///     client
+///         .delete_secret(&"SECRET_NAME")
+///         .await
+///         .unwrap()
+///         .with_interval(Duration::from_secs(1))
+///         .with_timeout(Duration::from_secs(30))
+///         .wait()
+///         .await
+///         .unwrap();
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+#[derive(Debug, Getters, CopyGetters)]
+pub struct SecretOperationPoller<'c, 'a> {
+    client: &'c mut KeyVaultClient<'a>,
+    #[getset(get_copy = "pub")]
+    operation: SecretOperation,
+    #[getset(get = "pub")]
+    secret_name: String,
+    #[getset(get_copy = "pub")]
+    interval: Duration,
+    #[getset(get_copy = "pub")]
+    timeout: Duration,
+    /// Whether a deleted secret is kept for recovery, rather than removed right away.
+    soft_delete: bool,
+}
+
+impl<'c, 'a> SecretOperationPoller<'c, 'a> {
+    pub(crate) fn new(client: &'c mut KeyVaultClient<'a>, operation: SecretOperation, secret_name: &str) -> Self {
+        Self {
+            client,
+            operation,
+            secret_name: secret_name.to_owned(),
+            interval: DEFAULT_POLLING_INTERVAL,
+            timeout: DEFAULT_POLLING_TIMEOUT,
+            soft_delete: true,
+        }
+    }
+
+    /// Sets whether the vault keeps deleted secrets for recovery, as told by the response to a deletion.
+    pub(crate) fn with_soft_delete(mut self, soft_delete: bool) -> Self {
+        self.soft_delete = soft_delete;
+        self
+    }
+
+    /// Whether the vault keeps deleted secrets for recovery, as told by the response to a deletion.
+    pub(crate) fn soft_delete(&self) -> bool {
+        self.soft_delete
+    }
+
+    /// Sets the time to wait between status checks.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the maximum time `wait` will poll for before giving up.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Checks once whether the operation has completed.
+    pub async fn poll(&mut self) -> Result<bool, KeyVaultError> {
+        // Deletions and recoveries complete once the target resource shows up, purges once it is gone.
+        // Without soft-delete there is no deleted secret to show up, so deletions complete once the secret is gone.
+        // Live secrets are looked up through their versions, which does not read the value: that would need
+        // the get permission, and fail when the latest version is disabled.
+        let (path, completes_when_found) = match self.operation {
+            SecretOperation::Delete if self.soft_delete => (format!("deletedsecrets/{}", self.secret_name), true),
+            SecretOperation::Delete => (format!("secrets/{}/versions", self.secret_name), false),
+            SecretOperation::Recover => (format!("secrets/{}/versions", self.secret_name), true),
+            SecretOperation::Purge => (format!("deletedsecrets/{}", self.secret_name), false),
+        };
+        let mut uri = Url::parse_with_params(
+            &format!("{}/{}", self.client.keyvault_endpoint, path),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+        if path.ends_with("/versions") {
+            uri.query_pairs_mut().append_pair("maxresults", "1");
+        }
+
+        match self.client.get_authed(uri.to_string()).await {
+            Ok(_) => Ok(completes_when_found),
+            Err(KeyVaultError::NotFound(_)) => Ok(!completes_when_found),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Polls until the operation has completed, or fails once the timeout has elapsed.
+    pub async fn wait(mut self) -> Result<(), KeyVaultError> {
+        let started = Instant::now();
+        loop {
+            if self.poll().await? {
+                return Ok(());
+            }
+            let elapsed = started.elapsed();
+            if elapsed >= self.timeout {
+                return Err(KeyVaultError::OperationTimedOut {
+                    operation: self.operation.to_string(),
+                    secret_name: self.secret_name,
+                    timeout: self.timeout,
+                });
+            }
+            tokio::time::delay_for(self.interval.min(self.timeout - elapsed)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn wait_for_delete() {
+        let _m = mock("DELETE", "/secrets/deleted-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "id": "https://test-keyvault.vault.azure.net/secrets/deleted-secret/VERSION",
+                    "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/deleted-secret",
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m2 = mock("GET", "/deletedsecrets/deleted-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "id": "https://test-keyvault.vault.azure.net/secrets/deleted-secret/VERSION",
+                    "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/deleted-secret",
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let poller = client.delete_secret("deleted-secret").await.unwrap();
+        assert_eq!(SecretOperation::Delete, poller.operation());
+        poller.wait().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn wait_for_delete_without_soft_delete() {
+        let _m = mock("DELETE", "/secrets/hard-deleted-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({ "id": "https://test-keyvault.vault.azure.net/secrets/hard-deleted-secret/VERSION" })
+                    .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m2 = mock("GET", "/secrets/hard-deleted-secret/versions")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "1".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }).to_string())
+            .with_status(404)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        client
+            .delete_secret("hard-deleted-secret")
+            .await
+            .unwrap()
+            .with_timeout(Duration::from_millis(50))
+            .wait()
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_delete_is_reported() {
+        let _m = mock("DELETE", "/secrets/forbidden-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "Forbidden", "message": "Access denied" } }).to_string())
+            .with_status(403)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.delete_secret("forbidden-secret").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+
+    #[tokio::test]
+    async fn wait_for_recover_times_out() {
+        let _m = mock("POST", "/deletedsecrets/recovering-secret/recover")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_status(200)
+            .create();
+        let _m2 = mock("GET", "/secrets/recovering-secret/versions")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "1".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "error": { "code": "SecretNotFound", "message": "Secret not found: recovering-secret" }
+                })
+                .to_string(),
+            )
+            .with_status(404)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client
+            .recover_deleted_secret("recovering-secret")
+            .await
+            .unwrap()
+            .with_interval(Duration::from_millis(10))
+            .with_timeout(Duration::from_millis(50))
+            .wait()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::OperationTimedOut { .. }));
+    }
+}
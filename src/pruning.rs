@@ -0,0 +1,315 @@
+use crate::report::{describe_error, SecretOutcomeStatus, VaultOperationReport};
+use crate::validation::validate_tags;
+use crate::{KeyVaultClient, KeyVaultError};
+use getset::Getters;
+use serde::Serialize;
+use serde_json::{json, Map};
+
+/// Options for [`prune_secret_versions`](KeyVaultClient::prune_secret_versions).
+#[derive(Debug, Clone, Default)]
+pub struct PruneOptions {
+    dry_run: bool,
+    tag: Option<(String, String)>,
+}
+
+impl PruneOptions {
+    /// Reports which versions would be disabled, without disabling them.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Tags the versions being disabled, for example to record why they were.
+    pub fn with_tag(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tag = Some((name.into(), value.into()));
+        self
+    }
+}
+
+/// The versions of a secret kept enabled and disabled by pruning, or to be disabled in a dry run.
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct PrunedSecret {
+    secret_name: String,
+    current_version: Option<String>,
+    /// The older versions which stay enabled, newest first.
+    kept_versions: Vec<String>,
+    /// The older versions disabled, newest first.
+    disabled_versions: Vec<String>,
+}
+
+/// The result of pruning the versions of every secret in a vault.
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct PruneReport {
+    secrets: Vec<PrunedSecret>,
+    outcomes: VaultOperationReport,
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Disables the older versions of a secret, keeping the `keep` latest versions enabled.
+    ///
+    /// The current version is never touched and always counts as one of the versions kept,
+    /// even if `keep` is zero. Versions which are already disabled are left alone.
+    /// This operation requires the secrets/list and secrets/update permissions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::pruning::PruneOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let options = PruneOptions::default().with_tag("pruned-by", "nightly-job").with_dry_run(true);
+    ///     let pruned = client.prune_secret_versions(&"SECRET_NAME", 3, &options).await.unwrap();
+    ///     println!("{}", serde_json::to_string_pretty(&pruned).unwrap());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn prune_secret_versions(
+        &mut self,
+        secret_name: &str,
+        keep: usize,
+        options: &PruneOptions,
+    ) -> Result<PrunedSecret, KeyVaultError> {
+        let mut versions = self.get_secret_versions(secret_name).await?;
+        // Disabling or tagging a version changes its update time, but never its creation time.
+        versions.sort_by(|a, b| b.time_created().cmp(a.time_created()));
+
+        let mut pruned = PrunedSecret {
+            secret_name: secret_name.to_owned(),
+            current_version: None,
+            kept_versions: Vec::new(),
+            disabled_versions: Vec::new(),
+        };
+        let (current, older) = match versions.split_first() {
+            Some(split) => split,
+            None => return Ok(pruned),
+        };
+        pruned.current_version = current.version().clone();
+
+        let older_enabled = older.iter().filter(|v| *v.enabled()).collect::<Vec<_>>();
+        let keep_older = keep.saturating_sub(1).min(older_enabled.len());
+        let (kept, to_disable) = older_enabled.split_at(keep_older);
+        pruned.kept_versions = kept.iter().filter_map(|v| v.version().clone()).collect();
+
+        for version in to_disable {
+            let version_name = version.version().clone().unwrap_or_default();
+            if !options.dry_run {
+                let mut request_body = Map::new();
+                request_body.insert("attributes".to_owned(), json!({ "enabled": false }));
+                if let Some((tag_name, tag_value)) = &options.tag {
+                    let mut tags = version.tags().clone();
+                    tags.insert(tag_name.clone(), tag_value.clone());
+                    validate_tags(secret_name, &tags)?;
+                    request_body.insert("tags".to_owned(), serde_json::to_value(tags).unwrap());
+                }
+                self.patch_secret(secret_name, &version_name, request_body).await?;
+            }
+            pruned.disabled_versions.push(version_name);
+        }
+        Ok(pruned)
+    }
+
+    /// Prunes the versions of every secret in the Key Vault, as done by
+    /// [`prune_secret_versions`](KeyVaultClient::prune_secret_versions).
+    /// Secrets which fail to be pruned are reported, and do not stop the others from being pruned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::pruning::PruneOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let report = client.prune_vault_versions(3, &PruneOptions::default()).await.unwrap();
+    ///     for secret in report.secrets() {
+    ///         println!("{}: disabled {:?}", secret.secret_name(), secret.disabled_versions());
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn prune_vault_versions(
+        &mut self,
+        keep: usize,
+        options: &PruneOptions,
+    ) -> Result<PruneReport, KeyVaultError> {
+        let mut report = PruneReport::default();
+        for secret in self.list_secrets().await? {
+            let status = match self.prune_secret_versions(secret.name(), keep, options).await {
+                Ok(pruned) => {
+                    let status = if pruned.disabled_versions.is_empty() {
+                        SecretOutcomeStatus::Skipped {
+                            reason: "nothing to prune".to_owned(),
+                        }
+                    } else if options.dry_run {
+                        SecretOutcomeStatus::Skipped {
+                            reason: "dry run".to_owned(),
+                        }
+                    } else {
+                        SecretOutcomeStatus::Succeeded
+                    };
+                    report.secrets.push(pruned);
+                    status
+                }
+                Err(e) => SecretOutcomeStatus::Failed {
+                    error: describe_error(&e),
+                },
+            };
+            report.outcomes.record(secret.name(), status);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::test_utils::mock_get;
+    use crate::validation::MAX_TAGS;
+
+    use mockito::{mock, Matcher};
+
+    #[tokio::test]
+    async fn prune_secret_versions() {
+        let versions = [
+            ("VERSION_1", true, 1_600_000_001),
+            ("VERSION_2", false, 1_600_000_002),
+            ("VERSION_3", true, 1_600_000_003),
+            ("VERSION_4", true, 1_600_000_004),
+        ]
+        .iter()
+        .map(|(version, enabled, created)| {
+            json!({
+                "id": format!("https://test-keyvault.vault.azure.net/secrets/pruned-secret/{}", version),
+                "tags": { "owner": "team-a" },
+                // Later updates must not make an older version look current.
+                "attributes": { "enabled": enabled, "created": created, "updated": 1_700_000_000 - created }
+            })
+        })
+        .collect::<Vec<_>>();
+        let _m = mock("GET", "/secrets/pruned-secret/versions")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": versions, "nextLink": null }).to_string())
+            .with_status(200)
+            .create();
+        let disable = mock("PATCH", "/secrets/pruned-secret/VERSION_1")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({
+                "attributes": { "enabled": false },
+                "tags": { "owner": "team-a", "pruned": "true" }
+            })))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let options = PruneOptions::default().with_tag("pruned", "true");
+        let pruned = client
+            .prune_secret_versions("pruned-secret", 2, &options.clone().with_dry_run(true))
+            .await
+            .unwrap();
+        assert_eq!(&Some("VERSION_4".to_owned()), pruned.current_version());
+        assert_eq!(&vec!["VERSION_3".to_owned()], pruned.kept_versions());
+        assert_eq!(&vec!["VERSION_1".to_owned()], pruned.disabled_versions());
+
+        client
+            .prune_secret_versions("pruned-secret", 2, &options)
+            .await
+            .unwrap();
+        disable.assert();
+    }
+
+    #[tokio::test]
+    async fn prune_vault_versions() {
+        let secrets = ["pruned-secret", "single-secret", "crowded-secret", "forbidden-secret"]
+            .iter()
+            .map(|name| {
+                json!({
+                    "id": format!("https://test-keyvault.vault.azure.net/secrets/{}", name),
+                    "attributes": { "enabled": true, "created": 1_600_000_000, "updated": 1_600_000_000 }
+                })
+            })
+            .collect::<Vec<_>>();
+        let _m1 = mock_get("/prune-vault/secrets", json!({ "value": secrets, "nextLink": null }));
+        let versions = |secret_name: &str, count: u64, tags: serde_json::Value| {
+            let versions = (1..=count)
+                .map(|i| {
+                    json!({
+                        "id": format!("https://test-keyvault.vault.azure.net/secrets/{}/VERSION_{}", secret_name, i),
+                        "tags": tags,
+                        "attributes": { "enabled": true, "created": 1_600_000_000 + i, "updated": 1_600_000_000 + i }
+                    })
+                })
+                .collect::<Vec<_>>();
+            mock_get(
+                &format!("/prune-vault/secrets/{}/versions", secret_name),
+                json!({ "value": versions, "nextLink": null }),
+            )
+        };
+        let _m2 = versions("pruned-secret", 2, json!({}));
+        let _m3 = versions("single-secret", 1, json!({}));
+        // The pruning tag would take the versions past the tag limit.
+        let crowded_tags = (0..MAX_TAGS)
+            .map(|i| (format!("tag-{}", i), json!("value")))
+            .collect::<serde_json::Map<_, _>>();
+        let _m4 = versions("crowded-secret", 2, serde_json::Value::Object(crowded_tags));
+        let _m5 = mock("GET", "/prune-vault/secrets/forbidden-secret/versions")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "error": { "code": "Forbidden", "message": "Access denied" } }).to_string())
+            .with_status(403)
+            .create();
+        let disable = mock("PATCH", "/prune-vault/secrets/pruned-secret/VERSION_1")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({
+                "attributes": { "enabled": false },
+                "tags": { "pruned": "true" }
+            })))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let crowded = mock("PATCH", "/prune-vault/secrets/crowded-secret/VERSION_1")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/prune-vault", mockito::server_url());
+
+        let report = client
+            .prune_vault_versions(1, &PruneOptions::default().with_tag("pruned", "true"))
+            .await
+            .unwrap();
+        let names = |outcomes: Vec<&crate::report::SecretOutcome>| {
+            outcomes.iter().map(|o| o.secret_name().clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["pruned-secret"], names(report.outcomes().succeeded().collect()));
+        assert_eq!(vec!["single-secret"], names(report.outcomes().skipped().collect()));
+        assert_eq!(
+            vec!["crowded-secret", "forbidden-secret"],
+            names(report.outcomes().failed().collect())
+        );
+        assert_eq!(2, report.secrets().len());
+        assert_eq!(&vec!["VERSION_1".to_owned()], report.secrets()[0].disabled_versions());
+        disable.assert();
+        crowded.assert();
+    }
+}
@@ -0,0 +1,330 @@
+use crate::identifier::KeyVaultCollection;
+use crate::{KeyVaultClient, KeyVaultError, KeyVaultIdentifier, SecretValue};
+use getset::Getters;
+use reqwest::Url;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+const APP_SERVICE_PREFIX: &str = "@Microsoft.KeyVault(";
+const URI_SCHEME: &str = "kv://";
+
+/// A reference to a secret in a Key Vault, as found in application settings and configuration.
+///
+/// The following formats are understood:
+///
+/// * `@Microsoft.KeyVault(SecretUri=https://{vault}.vault.azure.net/secrets/{name}[/{version}])`
+/// * `@Microsoft.KeyVault(VaultName={vault};SecretName={name}[;SecretVersion={version}])`
+/// * `{"uri": "https://{vault}.vault.azure.net/secrets/{name}[/{version}]"}`, as used by App Configuration
+/// * `kv://{vault}/{name}[/{version}]`
+///
+/// # Example
+///
+/// ```
+/// use azure_sdk_keyvault::reference::KeyVaultReference;
+///
+/// let reference: KeyVaultReference = "@Microsoft.KeyVault(VaultName=test-keyvault;SecretName=test-secret)"
+///     .parse()
+///     .unwrap();
+/// assert_eq!("test-keyvault", reference.vault_name());
+/// assert_eq!("test-secret", reference.secret_name());
+/// assert_eq!("kv://test-keyvault/test-secret", reference.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Getters)]
+#[getset(get = "pub")]
+pub struct KeyVaultReference {
+    vault_name: String,
+    secret_name: String,
+    /// The version of the secret, or `None` for the latest version.
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AppConfigurationReference {
+    uri: String,
+}
+
+impl KeyVaultReference {
+    /// Parses a reference in any of the supported formats.
+    pub fn parse(reference: &str) -> Result<Self, KeyVaultError> {
+        let invalid = |reason: &str| {
+            KeyVaultError::InvalidIdentifier(format!(
+                "'{}' is not a valid Key Vault reference: {}",
+                reference, reason
+            ))
+        };
+
+        let trimmed = reference.trim();
+        if let Some(uri) = trimmed.strip_prefix(URI_SCHEME) {
+            let mut segments = uri.split('/').collect::<Vec<_>>();
+            if segments.last() == Some(&"") {
+                segments.pop();
+            }
+            return match segments.as_slice() {
+                [vault_name, secret_name] => Self::new(vault_name, secret_name, None),
+                [vault_name, secret_name, version] => Self::new(vault_name, secret_name, Some(version)),
+                _ => None,
+            }
+            .ok_or_else(|| invalid("expected kv://{vault}/{name}[/{version}]"));
+        }
+
+        if let Some(arguments) = trimmed.strip_prefix(APP_SERVICE_PREFIX) {
+            let arguments = arguments
+                .strip_suffix(')')
+                .ok_or_else(|| invalid("missing closing parenthesis"))?;
+            let mut parameters = HashMap::new();
+            for parameter in arguments.split(';').filter(|p| !p.trim().is_empty()) {
+                let mut parts = parameter.splitn(2, '=');
+                let name = parts.next().unwrap_or_default().trim();
+                let value = parts.next().ok_or_else(|| invalid("expected Name=Value parameters"))?;
+                parameters.insert(name, value.trim());
+            }
+            if let Some(secret_uri) = parameters.get("SecretUri") {
+                return Self::from_secret_uri(secret_uri).ok_or_else(|| invalid("SecretUri is not a secret URL"));
+            }
+            return match (parameters.get("VaultName"), parameters.get("SecretName")) {
+                (Some(vault_name), Some(secret_name)) => {
+                    Self::new(vault_name, secret_name, parameters.get("SecretVersion").copied())
+                }
+                _ => None,
+            }
+            .ok_or_else(|| invalid("expected SecretUri, or VaultName and SecretName"));
+        }
+
+        if trimmed.starts_with('{') {
+            let app_configuration = serde_json::from_str::<AppConfigurationReference>(trimmed)
+                .map_err(|_| invalid("expected a JSON object with a uri"))?;
+            return Self::from_secret_uri(&app_configuration.uri).ok_or_else(|| invalid("uri is not a secret URL"));
+        }
+
+        Err(invalid("unknown format"))
+    }
+
+    /// Whether a value looks like a reference, in which case it should be resolved rather than used as is.
+    pub fn is_reference(value: &str) -> bool {
+        let trimmed = value.trim();
+        trimmed.starts_with(URI_SCHEME)
+            || trimmed.starts_with(APP_SERVICE_PREFIX)
+            || (trimmed.starts_with('{') && serde_json::from_str::<AppConfigurationReference>(trimmed).is_ok())
+    }
+
+    fn new(vault_name: &str, secret_name: &str, version: Option<&str>) -> Option<Self> {
+        if vault_name.is_empty() || secret_name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            vault_name: vault_name.to_owned(),
+            secret_name: secret_name.to_owned(),
+            version: version.filter(|v| !v.is_empty()).map(str::to_owned),
+        })
+    }
+
+    fn from_secret_uri(secret_uri: &str) -> Option<Self> {
+        let identifier = KeyVaultIdentifier::parse(secret_uri).ok()?;
+        if identifier.collection() != KeyVaultCollection::Secrets {
+            return None;
+        }
+        let vault_url = Url::parse(identifier.vault_url()).ok()?;
+        let vault_name = vault_url.host_str()?.split('.').next()?;
+        Self::new(vault_name, identifier.name(), identifier.version().as_deref())
+    }
+}
+
+impl FromStr for KeyVaultReference {
+    type Err = KeyVaultError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyVaultReference::parse(s)
+    }
+}
+
+impl fmt::Display for KeyVaultReference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}/{}", URI_SCHEME, self.vault_name, self.secret_name)?;
+        if let Some(version) = &self.version {
+            write!(f, "/{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Resolves [`KeyVaultReference`]s through a client for each Key Vault they may point into.
+///
+/// Resolved values are cached for the lifetime of the resolver, or until [`clear_cache`](ReferenceResolver::clear_cache)
+/// is called. References to the latest version of a secret keep resolving to the value first fetched.
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::reference::ReferenceResolver;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let client = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"KEYVAULT_NAME",
+///     );
+///     let mut resolver = ReferenceResolver::default().with_client(client);
+///     let setting = std::env::var("DATABASE_PASSWORD").unwrap();
+///     let password = resolver.resolve_value(&setting).await.unwrap();
+///     dbg!(password.expose().len());
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+#[derive(Debug, Default)]
+pub struct ReferenceResolver<'a> {
+    clients: HashMap<String, KeyVaultClient<'a>>,
+    cache: HashMap<KeyVaultReference, SecretValue>,
+}
+
+impl<'a> ReferenceResolver<'a> {
+    /// Adds the client used to resolve references into its Key Vault.
+    pub fn with_client(mut self, client: KeyVaultClient<'a>) -> Self {
+        self.clients.insert(client.keyvault_name.to_owned(), client);
+        self
+    }
+
+    /// Gets the value of the secret a reference points to.
+    pub async fn resolve(&mut self, reference: &KeyVaultReference) -> Result<SecretValue, KeyVaultError> {
+        if let Some(value) = self.cache.get(reference) {
+            return Ok(value.clone());
+        }
+        let client = self.clients.get_mut(&reference.vault_name).ok_or_else(|| {
+            KeyVaultError::GeneralError(format!(
+                "No client was given for Key Vault '{}', referenced by {}",
+                reference.vault_name, reference
+            ))
+        })?;
+        let secret = client
+            .get_secret_with_version(&reference.secret_name, reference.version.as_deref().unwrap_or_default())
+            .await?;
+        self.cache.insert(reference.clone(), secret.value().clone());
+        Ok(secret.value().clone())
+    }
+
+    /// Resolves a setting which may or may not be a reference: references are replaced with the value
+    /// of the secret they point to, and other values are returned as is.
+    pub async fn resolve_value(&mut self, value: &str) -> Result<SecretValue, KeyVaultError> {
+        if KeyVaultReference::is_reference(value) {
+            let reference = KeyVaultReference::parse(value)?;
+            self.resolve(&reference).await
+        } else {
+            Ok(SecretValue::from(value))
+        }
+    }
+
+    /// Forgets every resolved value, so that the next resolutions get the secrets again.
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    #[test]
+    fn parse_references() {
+        let latest = KeyVaultReference::new("test-keyvault", "test-secret", None).unwrap();
+        let versioned = KeyVaultReference::new("test-keyvault", "test-secret", Some("VERSION_1")).unwrap();
+        for (reference, expected) in &[
+            ("kv://test-keyvault/test-secret", &latest),
+            ("kv://test-keyvault/test-secret/VERSION_1", &versioned),
+            (
+                "@Microsoft.KeyVault(SecretUri=https://test-keyvault.vault.azure.net/secrets/test-secret/)",
+                &latest,
+            ),
+            (
+                "@Microsoft.KeyVault(SecretUri=https://test-keyvault.vault.azure.net/secrets/test-secret/VERSION_1)",
+                &versioned,
+            ),
+            (
+                "@Microsoft.KeyVault(VaultName=test-keyvault;SecretName=test-secret)",
+                &latest,
+            ),
+            (
+                "@Microsoft.KeyVault(VaultName=test-keyvault;SecretName=test-secret;SecretVersion=VERSION_1)",
+                &versioned,
+            ),
+            (
+                r#"{"uri":"https://test-keyvault.vault.azure.net/secrets/test-secret"}"#,
+                &latest,
+            ),
+        ] {
+            assert!(KeyVaultReference::is_reference(reference));
+            assert_eq!(*expected, &KeyVaultReference::parse(reference).unwrap());
+        }
+
+        assert!(!KeyVaultReference::is_reference("plain-value"));
+        assert!(!KeyVaultReference::is_reference(r#"{"name":"value"}"#));
+        for reference in &[
+            "kv://test-keyvault",
+            "@Microsoft.KeyVault(VaultName=test-keyvault)",
+            "@Microsoft.KeyVault(SecretUri=https://test-keyvault.vault.azure.net/keys/test-key)",
+            r#"{"uri":"not a url"}"#,
+        ] {
+            assert!(matches!(
+                KeyVaultReference::parse(reference),
+                Err(KeyVaultError::InvalidIdentifier(_))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_references() {
+        let get = mock("GET", "/reference/secrets/referenced-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "value": "secret-value",
+                    "id": "https://test-keyvault.vault.azure.net/secrets/referenced-secret/VERSION_1",
+                    "attributes": {
+                        "enabled": true,
+                        "created": 1_600_000_000,
+                        "updated": 1_600_000_000,
+                        "recoveryLevel": "Recoverable+Purgeable"
+                    }
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/reference", mockito::server_url());
+        let mut resolver = ReferenceResolver::default().with_client(client);
+
+        let value = resolver
+            .resolve_value("@Microsoft.KeyVault(VaultName=test-keyvault;SecretName=referenced-secret)")
+            .await
+            .unwrap();
+        assert_eq!("secret-value", value.expose());
+        let value = resolver
+            .resolve_value("kv://test-keyvault/referenced-secret")
+            .await
+            .unwrap();
+        assert_eq!("secret-value", value.expose());
+        assert_eq!(
+            "plain-value",
+            resolver.resolve_value("plain-value").await.unwrap().expose()
+        );
+        get.assert();
+
+        let err = resolver
+            .resolve_value("kv://other-keyvault/referenced-secret")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::KeyVaultError;
+use getset::Getters;
+use serde::Serialize;
+use std::error::Error;
+
+/// What happened to a single secret during a vault-wide operation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SecretOutcomeStatus {
+    Succeeded,
+    Skipped { reason: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct SecretOutcome {
+    secret_name: String,
+    #[serde(flatten)]
+    status: SecretOutcomeStatus,
+}
+
+/// Per-secret summary of a vault-wide operation, such as a vault backup or restore.
+#[derive(Debug, Clone, Default, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct VaultOperationReport {
+    outcomes: Vec<SecretOutcome>,
+}
+
+impl VaultOperationReport {
+    pub(crate) fn record(&mut self, secret_name: &str, status: SecretOutcomeStatus) {
+        self.outcomes.push(SecretOutcome {
+            secret_name: secret_name.to_owned(),
+            status,
+        });
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &SecretOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|o| matches!(o.status, SecretOutcomeStatus::Succeeded))
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &SecretOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|o| matches!(o.status, SecretOutcomeStatus::Skipped { .. }))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &SecretOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|o| matches!(o.status, SecretOutcomeStatus::Failed { .. }))
+    }
+
+    /// Whether no secret failed.
+    pub fn is_success(&self) -> bool {
+        self.failed().next().is_none()
+    }
+}
+
+/// Progress of a vault-wide operation, reported after each secret has been processed.
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
+pub struct VaultProgress {
+    secret_name: String,
+    completed: usize,
+    total: usize,
+}
+
+impl VaultProgress {
+    pub(crate) fn new(secret_name: &str, completed: usize, total: usize) -> Self {
+        Self {
+            secret_name: secret_name.to_owned(),
+            completed,
+            total,
+        }
+    }
+}
+
+/// Describes an error along with all its causes, since some variants only carry their detail as a source.
+pub(crate) fn describe_error(error: &KeyVaultError) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        description.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    description
+}
@@ -0,0 +1,418 @@
+use crate::secret::{KeyVaultSecretBaseIdentifier, SecretProperties};
+use crate::validation::validate_tags;
+use crate::{KeyVaultClient, KeyVaultError, SecretValue};
+use chrono::{DateTime, Duration, Utc};
+use getset::{CopyGetters, Getters};
+use serde::{Deserialize, Serialize};
+
+/// Tags a new version until the rotation which created it is complete, so that an interrupted rotation
+/// can be found and resumed from the vault itself.
+const DISABLE_AFTER_TAG: &str = "rotation-disable-after";
+const KEEP_ENABLED_TAG: &str = "rotation-keep-enabled";
+
+/// How a secret is rotated.
+#[derive(Debug, Clone, Getters, CopyGetters)]
+pub struct RotationPolicy {
+    /// How long older versions stay enabled after the new version is set. Defaults to a day.
+    #[getset(get = "pub")]
+    grace_period: Duration,
+    /// How many older versions stay enabled once the grace period is over. Defaults to none.
+    #[getset(get_copy = "pub")]
+    keep_enabled: usize,
+    /// How long the new version is valid for. Defaults to no expiry.
+    #[getset(get = "pub")]
+    expires_after: Option<Duration>,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            grace_period: Duration::days(1),
+            keep_enabled: 0,
+            expires_after: None,
+        }
+    }
+}
+
+impl RotationPolicy {
+    pub fn with_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    pub fn with_keep_enabled(mut self, keep_enabled: usize) -> Self {
+        self.keep_enabled = keep_enabled;
+        self
+    }
+
+    pub fn with_expires_after(mut self, expires_after: Duration) -> Self {
+        self.expires_after = Some(expires_after);
+        self
+    }
+}
+
+/// The progress of a rotation, which can be persisted and completed later on.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, CopyGetters, Serialize, Deserialize)]
+pub struct RotationRecord {
+    #[getset(get = "pub")]
+    secret_name: String,
+    /// The version created by the rotation.
+    #[getset(get = "pub")]
+    new_version: String,
+    #[getset(get = "pub")]
+    time_rotated: DateTime<Utc>,
+    /// When the older versions get disabled.
+    #[getset(get = "pub")]
+    time_disable_after: DateTime<Utc>,
+    #[getset(get_copy = "pub")]
+    keep_enabled: usize,
+    /// The older versions disabled so far.
+    #[getset(get = "pub")]
+    disabled_versions: Vec<String>,
+    #[getset(get_copy = "pub")]
+    completed: bool,
+}
+
+/// Rebuilds the record of a rotation from the tags of the latest version, if it is still pending.
+fn pending_rotation(secret_name: &str, versions: &[KeyVaultSecretBaseIdentifier]) -> Option<RotationRecord> {
+    let latest = versions.iter().max_by_key(|v| *v.time_created())?;
+    let time_disable_after = DateTime::parse_from_rfc3339(latest.tags().get(DISABLE_AFTER_TAG)?).ok()?;
+    let keep_enabled = latest.tags().get(KEEP_ENABLED_TAG)?.parse().ok()?;
+    Some(RotationRecord {
+        secret_name: secret_name.to_owned(),
+        new_version: latest.version().clone()?,
+        time_rotated: *latest.time_created(),
+        time_disable_after: time_disable_after.with_timezone(&Utc),
+        keep_enabled,
+        disabled_versions: Vec::new(),
+        completed: false,
+    })
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Rotates a secret: sets a new version generated by `generator`, and disables the older versions
+    /// once the grace period of the policy is over.
+    ///
+    /// The new version keeps the content type and tags of the current one. Unless the grace period is
+    /// zero, the rotation is only completed by calling [`complete_rotation`](KeyVaultClient::complete_rotation)
+    /// after the grace period. If a previous rotation of the secret is still pending, it is returned instead
+    /// of rotating again, so a rotation interrupted at any step is resumed by calling `rotate_secret` again.
+    ///
+    /// This operation requires the secrets/list, secrets/set and secrets/update permissions.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::{KeyVaultClient, SecretValue};
+    /// use azure_sdk_keyvault::rotation::RotationPolicy;
+    /// use chrono::Duration;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let policy = RotationPolicy::default()
+    ///         .with_grace_period(Duration::hours(12))
+    ///         .with_keep_enabled(1)
+    ///         .with_expires_after(Duration::days(90));
+    ///     let mut record = client
+    ///         .rotate_secret(&"SECRET_NAME", || SecretValue::from("NEW_VALUE"), &policy)
+    ///         .await
+    ///         .unwrap();
+    ///
+    ///     // Later on, once the grace period is over.
+    ///     client.complete_rotation(&mut record).await.unwrap();
+    ///     assert!(record.completed());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn rotate_secret(
+        &mut self,
+        secret_name: &str,
+        generator: impl FnOnce() -> SecretValue,
+        policy: &RotationPolicy,
+    ) -> Result<RotationRecord, KeyVaultError> {
+        let versions = self.get_secret_versions(secret_name).await?;
+        if let Some(record) = pending_rotation(secret_name, &versions) {
+            return Ok(record);
+        }
+
+        let current = versions.iter().max_by_key(|v| *v.time_created());
+        let mut tags = current.map(|v| v.tags().clone()).unwrap_or_default();
+        let now = Utc::now();
+        let time_disable_after = now + policy.grace_period;
+        tags.insert(DISABLE_AFTER_TAG.to_owned(), time_disable_after.to_rfc3339());
+        tags.insert(KEEP_ENABLED_TAG.to_owned(), policy.keep_enabled.to_string());
+        // The rotation tags come on top of those of the current version, which may leave no room for them.
+        validate_tags(secret_name, &tags)?;
+        let mut properties = SecretProperties::default().with_tags(tags);
+        if let Some(content_type) = current.and_then(|v| v.content_type().clone()) {
+            properties = properties.with_content_type(content_type);
+        }
+        if let Some(expires_after) = policy.expires_after {
+            properties = properties.with_expires(now + expires_after);
+        }
+
+        let new_secret = self
+            .set_secret_value_with_properties(secret_name, &generator(), &properties)
+            .await?;
+        let mut record = RotationRecord {
+            secret_name: secret_name.to_owned(),
+            new_version: new_secret.version().clone(),
+            time_rotated: *new_secret.time_created(),
+            time_disable_after,
+            keep_enabled: policy.keep_enabled,
+            disabled_versions: Vec::new(),
+            completed: false,
+        };
+        if policy.grace_period <= Duration::zero() {
+            self.complete_rotation(&mut record).await?;
+        }
+        Ok(record)
+    }
+
+    /// Finds a rotation of the secret which has not been completed yet.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     if let Some(mut record) = client.pending_rotation(&"SECRET_NAME").await.unwrap() {
+    ///         client.complete_rotation(&mut record).await.unwrap();
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn pending_rotation(&mut self, secret_name: &str) -> Result<Option<RotationRecord>, KeyVaultError> {
+        let versions = self.get_secret_versions(secret_name).await?;
+        Ok(pending_rotation(secret_name, &versions))
+    }
+
+    /// Completes a rotation once its grace period is over, disabling the older versions of the secret
+    /// beyond those kept enabled by the policy. Returns whether the rotation is complete.
+    ///
+    /// Calling it again after an interruption picks up where it left off.
+    pub async fn complete_rotation(&mut self, record: &mut RotationRecord) -> Result<bool, KeyVaultError> {
+        if record.completed {
+            return Ok(true);
+        }
+        if Utc::now() < record.time_disable_after {
+            return Ok(false);
+        }
+
+        let (new_version, mut older_versions): (Vec<_>, Vec<_>) = self
+            .get_secret_versions(&record.secret_name)
+            .await?
+            .into_iter()
+            .filter(|v| *v.time_created() <= record.time_rotated)
+            .partition(|v| v.version().as_ref() == Some(&record.new_version));
+        let new_version = new_version.into_iter().next().ok_or_else(|| {
+            KeyVaultError::NotFound(format!(
+                "The version {} of secret {} no longer exists",
+                record.new_version, record.secret_name
+            ))
+        })?;
+        older_versions.sort_by(|a, b| b.time_created().cmp(a.time_created()));
+        for version in older_versions.iter().filter(|v| *v.enabled()).skip(record.keep_enabled) {
+            let version = version.version().clone().unwrap_or_default();
+            self.update_secret_enabled(&record.secret_name, &version, false).await?;
+            record.disabled_versions.push(version);
+        }
+
+        // Removing the tags marks the rotation as complete in the vault.
+        let mut tags = new_version.tags().clone();
+        tags.remove(DISABLE_AFTER_TAG);
+        tags.remove(KEEP_ENABLED_TAG);
+        self.update_secret_tags(&record.secret_name, &record.new_version, &tags)
+            .await?;
+
+        record.completed = true;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::test_utils::secret_bundle;
+    use crate::validation::MAX_TAGS;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn secret_version(secret_name: &str, version: &str, created: i64, tags: serde_json::Value) -> serde_json::Value {
+        json!({
+            "id": format!("https://test-keyvault.vault.azure.net/secrets/{}/{}", secret_name, version),
+            "tags": tags,
+            "attributes": { "enabled": true, "created": created, "updated": created }
+        })
+    }
+
+    fn mock_versions(secret_name: &str, versions: Vec<serde_json::Value>) -> mockito::Mock {
+        mock("GET", format!("/secrets/{}/versions", secret_name).as_str())
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body(json!({ "value": versions, "nextLink": null }).to_string())
+            .with_status(200)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn rotate_secret_without_grace_period() {
+        let owner = json!({ "owner": "team-a" });
+        let pending_tags = json!({
+            "owner": "team-a",
+            DISABLE_AFTER_TAG: "2020-09-13T12:26:43+00:00",
+            KEEP_ENABLED_TAG: "1"
+        });
+        // The new version is only listed once it has been set.
+        let mut versions = vec![
+            secret_version("rotated-secret", "VERSION_1", 1_600_000_001, owner.clone()),
+            secret_version("rotated-secret", "VERSION_2", 1_600_000_002, owner.clone()),
+        ];
+        let listings = [json!({ "value": versions, "nextLink": null }).to_string(), {
+            versions.push(secret_version(
+                "rotated-secret",
+                "VERSION_3",
+                1_600_000_003,
+                pending_tags.clone(),
+            ));
+            json!({ "value": versions, "nextLink": null }).to_string()
+        }];
+        let listed = std::sync::atomic::AtomicUsize::new(0);
+        let _m1 = mock("GET", "/secrets/rotated-secret/versions")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_body_from_fn(move |w| {
+                let listing = listed.fetch_add(1, std::sync::atomic::Ordering::SeqCst).min(1);
+                w.write_all(listings[listing].as_bytes())
+            })
+            .with_status(200)
+            .create();
+        let mut new_version = secret_bundle("rotated-secret", "VERSION_3", "new-value");
+        new_version["tags"] = pending_tags;
+        new_version["attributes"]["created"] = json!(1_600_000_003);
+        let put = mock("PUT", "/secrets/rotated-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::PartialJson(json!({ "value": "new-value" })))
+            .with_body(new_version.to_string())
+            .with_status(200)
+            .expect(1)
+            .create();
+        let get = mock("GET", "/secrets/rotated-secret/VERSION_3")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create();
+        let disable = mock("PATCH", "/secrets/rotated-secret/VERSION_1")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "attributes": { "enabled": false } })))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let untag = mock("PATCH", "/secrets/rotated-secret/VERSION_3")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "tags": owner })))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let policy = RotationPolicy::default()
+            .with_grace_period(Duration::zero())
+            .with_keep_enabled(1);
+        let record = client
+            .rotate_secret("rotated-secret", || SecretValue::from("new-value"), &policy)
+            .await
+            .unwrap();
+        assert_eq!("VERSION_3", record.new_version());
+        assert_eq!(&vec!["VERSION_1".to_owned()], record.disabled_versions());
+        assert!(record.completed());
+        put.assert();
+        get.assert();
+        disable.assert();
+        untag.assert();
+    }
+
+    #[tokio::test]
+    async fn rotate_secret_checks_the_tag_limit() {
+        let tags = (0..MAX_TAGS - 1)
+            .map(|i| (format!("tag-{}", i), "value".to_owned()))
+            .collect::<HashMap<_, _>>();
+        let _m = mock_versions(
+            "crowded-secret",
+            vec![secret_version(
+                "crowded-secret",
+                "VERSION_1",
+                1_600_000_001,
+                json!(tags),
+            )],
+        );
+        let put = mock("PUT", "/secrets/crowded-secret")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client
+            .rotate_secret(
+                "crowded-secret",
+                || SecretValue::from("new-value"),
+                &RotationPolicy::default(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidSecret { .. }));
+        put.assert();
+    }
+
+    #[tokio::test]
+    async fn rotate_secret_resumes_pending_rotation() {
+        let _m = mock_versions(
+            "pending-secret",
+            vec![
+                secret_version("pending-secret", "VERSION_1", 1_600_000_001, json!({})),
+                secret_version(
+                    "pending-secret",
+                    "VERSION_2",
+                    1_600_000_002,
+                    json!({ DISABLE_AFTER_TAG: "2100-01-01T00:00:00+00:00", KEEP_ENABLED_TAG: "0" }),
+                ),
+            ],
+        );
+        let put = mock("PUT", "/secrets/pending-secret")
+            .match_query(Matcher::Any)
+            .expect(0)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let mut record = client
+            .rotate_secret(
+                "pending-secret",
+                || SecretValue::from("new-value"),
+                &RotationPolicy::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!("VERSION_2", record.new_version());
+        assert!(!client.complete_rotation(&mut record).await.unwrap());
+        put.assert();
+    }
+}
@@ -1,13 +1,22 @@
-use crate::KeyVaultClient;
-use crate::{client::API_VERSION, KeyVaultError};
+use crate::client::API_VERSION;
+use crate::identifier::{KeyVaultCollection, KeyVaultIdentifier};
+use crate::paging::{ContinuationToken, KeyVaultPage};
+use crate::poller::{SecretOperation, SecretOperationPoller};
+use crate::validation::{validate_secret_name, validate_secret_value, validate_secret_version, validate_tags};
+use crate::KeyVaultError;
+use crate::{KeyVaultClient, SecretValue};
 use anyhow::{Context, Result};
-use chrono::serde::ts_seconds;
+use chrono::serde::{ts_seconds, ts_seconds_option};
 use chrono::{DateTime, Utc};
+use futures::future;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
 use getset::Getters;
 use reqwest::Url;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::fmt;
+use zeroize::Zeroizing;
 
 const DEFAULT_MAX_RESULTS: usize = 25;
 
@@ -35,6 +44,10 @@ impl fmt::Display for RecoveryLevel {
 #[derive(Deserialize, Debug)]
 pub(crate) struct KeyVaultSecretBaseIdentifierAttributedRaw {
     enabled: bool,
+    #[serde(default, with = "ts_seconds_option")]
+    nbf: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_seconds_option")]
+    exp: Option<DateTime<Utc>>,
     #[serde(with = "ts_seconds")]
     created: DateTime<Utc>,
     #[serde(with = "ts_seconds")]
@@ -44,6 +57,10 @@ pub(crate) struct KeyVaultSecretBaseIdentifierAttributedRaw {
 #[derive(Deserialize, Debug)]
 pub(crate) struct KeyVaultSecretBaseIdentifierRaw {
     id: String,
+    #[serde(rename = "contentType")]
+    content_type: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
     attributes: KeyVaultSecretBaseIdentifierAttributedRaw,
 }
 
@@ -54,24 +71,104 @@ pub(crate) struct KeyVaultGetSecretsResponse {
     next_link: Option<String>,
 }
 
+#[derive(Deserialize, Debug)]
+pub(crate) struct KeyVaultDeletedSecretBaseIdentifierRaw {
+    id: String,
+    #[serde(rename = "recoveryId")]
+    recovery_id: String,
+    #[serde(rename = "deletedDate", with = "ts_seconds")]
+    deleted_date: DateTime<Utc>,
+    #[serde(rename = "scheduledPurgeDate", with = "ts_seconds")]
+    scheduled_purge_date: DateTime<Utc>,
+    attributes: KeyVaultSecretBaseIdentifierAttributedRaw,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct KeyVaultGetDeletedSecretsResponse {
+    value: Vec<KeyVaultDeletedSecretBaseIdentifierRaw>,
+    #[serde(rename = "nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct KeyVaultDeleteSecretResponseRaw {
+    #[serde(rename = "recoveryId")]
+    recovery_id: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct KeyVaultGetSecretResponse {
-    value: String,
+    value: SecretValue,
     id: String,
+    #[serde(rename = "contentType")]
+    content_type: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
     attributes: KeyVaultGetSecretResponseAttributes,
 }
 
+impl KeyVaultGetSecretResponse {
+    fn into_secret(self) -> Result<KeyVaultSecret, KeyVaultError> {
+        let identifier = KeyVaultIdentifier::parse(&self.id)?;
+        Ok(KeyVaultSecret {
+            name: identifier.name().to_owned(),
+            version: identifier.version().clone().unwrap_or_default(),
+            enabled: self.attributes.enabled,
+            value: self.value,
+            content_type: self.content_type,
+            tags: self.tags,
+            time_not_before: self.attributes.nbf,
+            time_expires: self.attributes.exp,
+            time_created: self.attributes.created,
+            time_updated: self.attributes.updated,
+            id: self.id,
+        })
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct KeyVaultGetSecretResponseAttributes {
     enabled: bool,
+    #[serde(default, with = "ts_seconds_option")]
+    nbf: Option<DateTime<Utc>>,
+    #[serde(default, with = "ts_seconds_option")]
+    exp: Option<DateTime<Utc>>,
     #[serde(with = "ts_seconds")]
     created: DateTime<Utc>,
     #[serde(with = "ts_seconds")]
     updated: DateTime<Utc>,
     #[serde(rename = "recoveryLevel")]
+    #[allow(dead_code)]
     recovery_level: String,
 }
 
+#[derive(Serialize)]
+pub(crate) struct KeyVaultSetSecretRequest<'b> {
+    value: &'b str,
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    content_type: Option<&'b str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tags: Option<&'b HashMap<String, String>>,
+    #[serde(skip_serializing_if = "KeyVaultSetSecretRequestAttributes::is_empty")]
+    attributes: KeyVaultSetSecretRequestAttributes,
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct KeyVaultSetSecretRequestAttributes {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "ts_seconds_option")]
+    nbf: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "ts_seconds_option")]
+    exp: Option<DateTime<Utc>>,
+}
+
+impl KeyVaultSetSecretRequestAttributes {
+    fn is_empty(&self) -> bool {
+        self.enabled.is_none() && self.nbf.is_none() && self.exp.is_none()
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub(crate) struct KeyVaultSecretBackupResponseRaw {
     value: String,
@@ -83,26 +180,119 @@ pub struct KeyVaultSecretBackupBlob {
     value: String,
 }
 
-#[derive(Debug, Getters)]
+#[derive(Debug, Clone, Getters)]
 #[getset(get = "pub")]
 pub struct KeyVaultSecretBaseIdentifier {
     id: String,
     name: String,
+    /// The version of the secret, when listing the versions of a secret.
+    version: Option<String>,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
     enabled: bool,
+    time_not_before: Option<DateTime<Utc>>,
+    time_expires: Option<DateTime<Utc>>,
     time_created: DateTime<Utc>,
     time_updated: DateTime<Utc>,
 }
 
 #[derive(Debug, Getters)]
 #[getset(get = "pub")]
+pub struct KeyVaultDeletedSecretBaseIdentifier {
+    id: String,
+    name: String,
+    recovery_id: String,
+    enabled: bool,
+    time_created: DateTime<Utc>,
+    time_updated: DateTime<Utc>,
+    time_deleted: DateTime<Utc>,
+    time_scheduled_purge: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Getters)]
+#[getset(get = "pub")]
 pub struct KeyVaultSecret {
     id: String,
-    value: String,
+    name: String,
+    version: String,
+    value: SecretValue,
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
     enabled: bool,
+    time_not_before: Option<DateTime<Utc>>,
+    time_expires: Option<DateTime<Utc>>,
     time_created: DateTime<Utc>,
     time_updated: DateTime<Utc>,
 }
 
+/// Properties of a new secret version, besides its value.
+///
+/// # Example
+///
+/// ```
+/// use azure_sdk_keyvault::secret::SecretProperties;
+/// use chrono::{Duration, Utc};
+///
+/// let properties = SecretProperties::default()
+///     .with_content_type("text/plain")
+///     .with_tag("owner", "team-a")
+///     .with_expires(Utc::now() + Duration::days(90));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Getters)]
+#[getset(get = "pub")]
+pub struct SecretProperties {
+    content_type: Option<String>,
+    tags: HashMap<String, String>,
+    /// Whether the version is enabled. Key Vault enables new versions when this is not set.
+    enabled: Option<bool>,
+    not_before: Option<DateTime<Utc>>,
+    expires: Option<DateTime<Utc>>,
+}
+
+impl SecretProperties {
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    pub fn with_tag(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_tags(mut self, tags: HashMap<String, String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = Some(enabled);
+        self
+    }
+
+    pub fn with_not_before(mut self, not_before: DateTime<Utc>) -> Self {
+        self.not_before = Some(not_before);
+        self
+    }
+
+    pub fn with_expires(mut self, expires: DateTime<Utc>) -> Self {
+        self.expires = Some(expires);
+        self
+    }
+}
+
+impl From<&KeyVaultSecret> for SecretProperties {
+    fn from(secret: &KeyVaultSecret) -> Self {
+        Self {
+            content_type: secret.content_type.clone(),
+            tags: secret.tags.clone(),
+            enabled: Some(secret.enabled),
+            not_before: secret.time_not_before,
+            expires: secret.time_expires,
+        }
+    }
+}
+
 impl<'a> KeyVaultClient<'a> {
     /// Gets a secret from the Key Vault.
     /// Note that the latest version is fetched. For a specific version, use `get_version_with_version`.
@@ -126,8 +316,8 @@ impl<'a> KeyVaultClient<'a> {
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn get_secret(&mut self, secret_name: &'a str) -> Result<KeyVaultSecret, KeyVaultError> {
-        Ok(self.get_secret_with_version(secret_name, "").await?)
+    pub async fn get_secret(&mut self, secret_name: &str) -> Result<KeyVaultSecret, KeyVaultError> {
+        self.get_secret_with_version(secret_name, "").await
     }
 
     /// Gets a secret from the Key Vault with a specific version.
@@ -154,9 +344,10 @@ impl<'a> KeyVaultClient<'a> {
     /// ```
     pub async fn get_secret_with_version(
         &mut self,
-        secret_name: &'a str,
-        secret_version_name: &'a str,
+        secret_name: &str,
+        secret_version_name: &str,
     ) -> Result<KeyVaultSecret, KeyVaultError> {
+        validate_secret_version(secret_name, secret_version_name)?;
         let uri = Url::parse_with_params(
             &format!(
                 "{}/secrets/{}/{}",
@@ -165,16 +356,58 @@ impl<'a> KeyVaultClient<'a> {
             &[("api-version", API_VERSION)],
         )
         .unwrap();
-        let resp_body = self.get_authed(uri.to_string()).await?;
-        let response = serde_json::from_str::<KeyVaultGetSecretResponse>(&resp_body)
-            .with_context(|| format!("Failed to parse response from Key Vault: {}", resp_body))?;
-        Ok(KeyVaultSecret {
-            enabled: response.attributes.enabled,
-            value: response.value,
-            time_created: response.attributes.created,
-            time_updated: response.attributes.updated,
-            id: response.id,
-        })
+        let resp_body = Zeroizing::new(self.get_authed(uri.to_string()).await?);
+        // The response body holds the secret value, so it is never echoed back in errors.
+        let response = serde_json::from_str::<KeyVaultGetSecretResponse>(&resp_body).with_context(|| {
+            format!(
+                "Failed to parse response from Key Vault when getting secret {}",
+                secret_name
+            )
+        })?;
+        response.into_secret()
+    }
+
+    /// Gets a secret from the Key Vault by its identifier, such as the `id` of a listed secret version.
+    /// The latest version is fetched if the identifier does not include a version.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let secret = client
+    ///         .get_secret_by_id(&"https://KEYVAULT_NAME.vault.azure.net/secrets/SECRET_NAME/SECRET_VERSION")
+    ///         .await
+    ///         .unwrap();
+    ///     dbg!(&secret);
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn get_secret_by_id(&mut self, secret_id: &str) -> Result<KeyVaultSecret, KeyVaultError> {
+        let identifier = KeyVaultIdentifier::parse(secret_id)?;
+        if identifier.collection() != KeyVaultCollection::Secrets {
+            return Err(KeyVaultError::InvalidIdentifier(format!(
+                "'{}' does not identify a secret",
+                secret_id
+            )));
+        }
+        if !identifier.is_in_vault(&self.keyvault_endpoint) {
+            return Err(KeyVaultError::InvalidIdentifier(format!(
+                "'{}' does not belong to the Key Vault at '{}'",
+                secret_id, self.keyvault_endpoint
+            )));
+        }
+        self.get_secret_with_version(identifier.name(), identifier.version().as_deref().unwrap_or(""))
+            .await
     }
 
     /// Lists all the secrets in the Key Vault.
@@ -197,41 +430,44 @@ impl<'a> KeyVaultClient<'a> {
     /// Runtime::new().unwrap().block_on(example());
     /// ```
     pub async fn list_secrets(&mut self) -> Result<Vec<KeyVaultSecretBaseIdentifier>, KeyVaultError> {
-        let mut secrets = Vec::<KeyVaultSecretBaseIdentifier>::new();
-        let mut uri = Url::parse_with_params(
-            &format!("{}/secrets", self.keyvault_endpoint),
-            &[
-                ("api-version", API_VERSION),
-                ("maxresults", &DEFAULT_MAX_RESULTS.to_string()),
-            ],
-        )
-        .unwrap();
-
-        loop {
-            let resp_body = self.get_authed(uri.to_string()).await?;
-            let response = serde_json::from_str::<KeyVaultGetSecretsResponse>(&resp_body).unwrap();
-
-            secrets.extend(
-                response
-                    .value
-                    .into_iter()
-                    .map(|s| KeyVaultSecretBaseIdentifier {
-                        id: s.id.to_owned(),
-                        name: s.id.to_owned().split("/").last().unwrap().to_owned(),
-                        enabled: s.attributes.enabled,
-                        time_created: s.attributes.created,
-                        time_updated: s.attributes.updated,
-                    })
-                    .collect::<Vec<KeyVaultSecretBaseIdentifier>>(),
-            );
-
-            match response.next_link {
-                None => break,
-                Some(u) => uri = Url::parse(&u).unwrap(),
-            }
-        }
+        self.list_secrets_stream(DEFAULT_MAX_RESULTS).try_collect().await
+    }
 
-        Ok(secrets)
+    /// Lists the secrets in the Key Vault as a stream, fetching one page at a time.
+    /// Pages are only requested as the stream is consumed, so dropping it stops the listing early.
+    ///
+    /// # Arguments
+    ///
+    /// * `page_size` - Number of secrets to request per page, between 1 and 25
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use futures::TryStreamExt;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let mut secrets = client.list_secrets_stream(10);
+    ///     while let Some(secret) = secrets.try_next().await.unwrap() {
+    ///         dbg!(&secret);
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub fn list_secrets_stream<'c>(
+        &'c mut self,
+        page_size: usize,
+    ) -> BoxStream<'c, Result<KeyVaultSecretBaseIdentifier, KeyVaultError>> {
+        let uri = format!("{}/secrets", self.keyvault_endpoint);
+        self.stream_secret_identifiers(uri, page_size)
     }
 
     /// Gets all the versions for a secret in the Key Vault.
@@ -257,40 +493,14 @@ impl<'a> KeyVaultClient<'a> {
     /// ```
     pub async fn get_secret_versions(
         &mut self,
-        secret_name: &'a str,
+        secret_name: &str,
     ) -> Result<Vec<KeyVaultSecretBaseIdentifier>, KeyVaultError> {
-        let mut secret_versions = Vec::<KeyVaultSecretBaseIdentifier>::new();
-        let mut uri = Url::parse_with_params(
-            &format!("{}/secrets/{}/versions", self.keyvault_endpoint, secret_name),
-            &[
-                ("api-version", API_VERSION),
-                ("maxresults", &DEFAULT_MAX_RESULTS.to_string()),
-            ],
-        )
-        .unwrap();
-
-        loop {
-            let resp_body = self.get_authed(uri.to_string()).await?;
-            let response = serde_json::from_str::<KeyVaultGetSecretsResponse>(&resp_body).unwrap();
-
-            secret_versions.extend(
-                response
-                    .value
-                    .into_iter()
-                    .map(|s| KeyVaultSecretBaseIdentifier {
-                        id: s.id.to_owned(),
-                        name: s.id.to_owned().split("/").last().unwrap().to_owned(),
-                        enabled: s.attributes.enabled,
-                        time_created: s.attributes.created,
-                        time_updated: s.attributes.updated,
-                    })
-                    .collect::<Vec<KeyVaultSecretBaseIdentifier>>(),
-            );
-            match response.next_link {
-                None => break,
-                Some(u) => uri = Url::parse(&u).unwrap(),
-            }
-        }
+        validate_secret_name(secret_name)?;
+        let uri = format!("{}/secrets/{}/versions", self.keyvault_endpoint, secret_name);
+        let mut secret_versions: Vec<KeyVaultSecretBaseIdentifier> = self
+            .stream_secret_identifiers(uri, DEFAULT_MAX_RESULTS)
+            .try_collect()
+            .await?;
 
         // Return the secret versions sorted by the time modified in descending order.
         secret_versions.sort_by(|a, b| {
@@ -303,12 +513,19 @@ impl<'a> KeyVaultClient<'a> {
         Ok(secret_versions)
     }
 
-    /// Sets the value of a secret in the Key Vault.
+    /// Gets the versions for a secret in the Key Vault as a stream, fetching one page at a time.
+    /// Unlike `get_secret_versions`, versions are yielded in the order the service returns them.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the secret
+    /// * `page_size` - Number of versions to request per page, between 1 and 25
     ///
     /// # Example
     ///
     /// ```no_run
     /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use futures::TryStreamExt;
     /// use tokio::runtime::Runtime;
     ///
     /// async fn example() {
@@ -318,34 +535,36 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.set_secret(&"SECRET_NAME", &"NEW_VALUE").await.unwrap();
+    ///     let enabled_versions: Vec<_> = client
+    ///         .get_secret_versions_stream(&"SECRET_NAME", 25)
+    ///         .try_filter(|v| futures::future::ready(*v.enabled()))
+    ///         .try_collect()
+    ///         .await
+    ///         .unwrap();
+    ///     dbg!(&enabled_versions);
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn set_secret(&mut self, secret_name: &'a str, new_secret_value: &'a str) -> Result<(), KeyVaultError> {
-        let uri = Url::parse_with_params(
-            &format!("{}/secrets/{}", self.keyvault_endpoint, secret_name),
-            &[("api-version", API_VERSION)],
-        )
-        .unwrap();
-
-        let mut request_body = Map::new();
-        request_body.insert("value".to_owned(), Value::String(new_secret_value.to_owned()));
-
-        self.put_authed(uri.to_string(), Value::Object(request_body).to_string())
-            .await?;
-
-        Ok(())
+    pub fn get_secret_versions_stream<'c>(
+        &'c mut self,
+        secret_name: &str,
+        page_size: usize,
+    ) -> BoxStream<'c, Result<KeyVaultSecretBaseIdentifier, KeyVaultError>> {
+        if let Err(e) = validate_secret_name(secret_name) {
+            return stream::once(future::ready(Err(e))).boxed();
+        }
+        let uri = format!("{}/secrets/{}/versions", self.keyvault_endpoint, secret_name);
+        self.stream_secret_identifiers(uri, page_size)
     }
 
-    /// Updates whether a secret version is enabled or not.
+    /// Gets a single page of the secrets in the Key Vault, along with a token to resume from the next page.
+    /// Useful for long-running jobs which need to pick up a listing where they left off.
     ///
     /// # Arguments
     ///
-    /// * `secret_name` - Name of the secret
-    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
-    /// * `enabled` - New `enabled` value of the secret
+    /// * `page_size` - Number of secrets to request per page, between 1 and 25. Ignored when resuming
+    /// * `continuation_token` - Token from a previously fetched page, or `None` to start from the first page
     ///
     /// # Example
     ///
@@ -360,37 +579,46 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.update_secret_enabled(&"SECRET_NAME", &"", true).await.unwrap();
+    ///     let mut continuation_token = None;
+    ///     loop {
+    ///         let page = client.list_secrets_page(25, continuation_token.as_ref()).await.unwrap();
+    ///         let (secrets, next) = page.into_parts();
+    ///         dbg!(&secrets);
+    ///         match next {
+    ///             // Persist the token somewhere to be able to resume from here.
+    ///             Some(token) => continuation_token = Some(token),
+    ///             None => break,
+    ///         }
+    ///     }
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn update_secret_enabled(
+    pub async fn list_secrets_page(
         &mut self,
-        secret_name: &'a str,
-        secret_version: &'a str,
-        enabled: bool,
-    ) -> Result<(), KeyVaultError> {
-        let mut attributes = Map::new();
-        attributes.insert("enabled".to_owned(), Value::Bool(enabled));
-
-        self.update_secret(secret_name, secret_version, attributes).await?;
-
-        Ok(())
+        page_size: usize,
+        continuation_token: Option<&ContinuationToken>,
+    ) -> Result<KeyVaultPage<KeyVaultSecretBaseIdentifier>, KeyVaultError> {
+        let uri = self.listing_uri(
+            format!("{}/secrets", self.keyvault_endpoint),
+            page_size,
+            continuation_token,
+        )?;
+        self.get_secret_identifiers_page(uri).await
     }
 
-    /// Updates the [`RecoveryLevel`](RecoveryLevel) of a secret version.
+    /// Gets a single page of the versions of a secret, along with a token to resume from the next page.
     ///
     /// # Arguments
     ///
     /// * `secret_name` - Name of the secret
-    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
-    /// * `recovery_level` - New `RecoveryLevel`(RecoveryLevel) value of the secret
+    /// * `page_size` - Number of versions to request per page, between 1 and 25. Ignored when resuming
+    /// * `continuation_token` - Token from a previously fetched page, or `None` to start from the first page
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use azure_sdk_keyvault::{KeyVaultClient, RecoveryLevel};
+    /// use azure_sdk_keyvault::KeyVaultClient;
     /// use tokio::runtime::Runtime;
     ///
     /// async fn example() {
@@ -400,39 +628,78 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.update_secret_recovery_level(&"SECRET_NAME", &"", RecoveryLevel::Purgeable).await.unwrap();
+    ///     let page = client.get_secret_versions_page(&"SECRET_NAME", 25, None).await.unwrap();
+    ///     dbg!(page.items(), page.continuation_token());
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn update_secret_recovery_level(
+    pub async fn get_secret_versions_page(
         &mut self,
-        secret_name: &'a str,
-        secret_version: &'a str,
-        recovery_level: RecoveryLevel,
-    ) -> Result<(), KeyVaultError> {
-        let mut attributes = Map::new();
-        attributes.insert("enabled".to_owned(), Value::String(recovery_level.to_string()));
-
-        self.update_secret(secret_name, secret_version, attributes).await?;
+        secret_name: &str,
+        page_size: usize,
+        continuation_token: Option<&ContinuationToken>,
+    ) -> Result<KeyVaultPage<KeyVaultSecretBaseIdentifier>, KeyVaultError> {
+        validate_secret_name(secret_name)?;
+        let uri = self.listing_uri(
+            format!("{}/secrets/{}/versions", self.keyvault_endpoint, secret_name),
+            page_size,
+            continuation_token,
+        )?;
+        self.get_secret_identifiers_page(uri).await
+    }
 
-        Ok(())
+    /// Lists all the deleted secrets in the Key Vault.
+    /// This operation requires the secrets/list permission, and is only available in vaults with soft-delete enabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let deleted_secrets = client.list_deleted_secrets().await.unwrap();
+    ///     dbg!(&deleted_secrets);
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn list_deleted_secrets(&mut self) -> Result<Vec<KeyVaultDeletedSecretBaseIdentifier>, KeyVaultError> {
+        let mut deleted_secrets = Vec::<KeyVaultDeletedSecretBaseIdentifier>::new();
+        let mut continuation_token = None;
+        loop {
+            let (page, next) = self
+                .list_deleted_secrets_page(DEFAULT_MAX_RESULTS, continuation_token.as_ref())
+                .await?
+                .into_parts();
+            deleted_secrets.extend(page);
+            match next {
+                None => break,
+                Some(token) => continuation_token = Some(token),
+            }
+        }
+        Ok(deleted_secrets)
     }
 
-    /// Updates the expiration time of a secret version.
+    /// Gets a single page of the deleted secrets in the Key Vault, along with a token to resume from the next page.
     ///
     /// # Arguments
     ///
-    /// * `secret_name` - Name of the secret
-    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
-    /// * `expiration_time - New expiration time of the secret
+    /// * `page_size` - Number of deleted secrets to request per page, between 1 and 25. Ignored when resuming
+    /// * `continuation_token` - Token from a previously fetched page, or `None` to start from the first page
     ///
     /// # Example
     ///
     /// ```no_run
-    /// use azure_sdk_keyvault::{KeyVaultClient, RecoveryLevel};
+    /// use azure_sdk_keyvault::KeyVaultClient;
     /// use tokio::runtime::Runtime;
-    /// use chrono::{Utc, Duration};
     ///
     /// async fn example() {
     ///     let mut client = KeyVaultClient::new(
@@ -441,51 +708,150 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.update_secret_expiration_time(&"SECRET_NAME", &"", Utc::now() + Duration::days(14)).await.unwrap();
+    ///     let page = client.list_deleted_secrets_page(25, None).await.unwrap();
+    ///     dbg!(page.items(), page.continuation_token());
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn update_secret_expiration_time(
+    pub async fn list_deleted_secrets_page(
         &mut self,
-        secret_name: &'a str,
-        secret_version: &'a str,
-        expiration_time: DateTime<Utc>,
-    ) -> Result<(), KeyVaultError> {
-        let mut attributes = Map::new();
-        attributes.insert(
-            "exp".to_owned(),
-            Value::Number(serde_json::Number::from(expiration_time.timestamp())),
-        );
+        page_size: usize,
+        continuation_token: Option<&ContinuationToken>,
+    ) -> Result<KeyVaultPage<KeyVaultDeletedSecretBaseIdentifier>, KeyVaultError> {
+        let uri = self.listing_uri(
+            format!("{}/deletedsecrets", self.keyvault_endpoint),
+            page_size,
+            continuation_token,
+        )?;
+        let resp_body = self.get_authed(uri.to_string()).await?;
+        let response = serde_json::from_str::<KeyVaultGetDeletedSecretsResponse>(&resp_body)
+            .with_context(|| format!("Failed to parse response from Key Vault: {}", resp_body))?;
 
-        self.update_secret(secret_name, secret_version, attributes).await?;
+        let page = response
+            .value
+            .into_iter()
+            .map(|s| {
+                Ok(KeyVaultDeletedSecretBaseIdentifier {
+                    name: KeyVaultIdentifier::parse(&s.id)?.name().to_owned(),
+                    id: s.id,
+                    recovery_id: s.recovery_id,
+                    enabled: s.attributes.enabled,
+                    time_created: s.attributes.created,
+                    time_updated: s.attributes.updated,
+                    time_deleted: s.deleted_date,
+                    time_scheduled_purge: s.scheduled_purge_date,
+                })
+            })
+            .collect::<Result<Vec<_>, KeyVaultError>>()?;
+        Ok(KeyVaultPage::new(page, response.next_link))
+    }
 
-        Ok(())
+    /// Lazily follows the `nextLink` of a secret listing, yielding the identifiers of each page.
+    fn stream_secret_identifiers<'c>(
+        &'c mut self,
+        uri: String,
+        page_size: usize,
+    ) -> BoxStream<'c, Result<KeyVaultSecretBaseIdentifier, KeyVaultError>> {
+        let first_uri = match self.listing_uri(uri.clone(), page_size, None) {
+            Ok(first_uri) => first_uri,
+            Err(e) => return stream::once(future::ready(Err(e))).boxed(),
+        };
+
+        stream::try_unfold((self, Some(first_uri)), move |(client, next_uri)| {
+            let uri = uri.clone();
+            async move {
+                let page_uri = match next_uri {
+                    None => return Ok(None),
+                    Some(page_uri) => page_uri,
+                };
+                let (page, next) = client.get_secret_identifiers_page(page_uri).await?.into_parts();
+                let next_uri = match next {
+                    Some(token) => Some(client.continuation_uri(&uri, &token)?),
+                    None => None,
+                };
+                Ok::<_, KeyVaultError>(Some((stream::iter(page.into_iter().map(Ok)), (client, next_uri))))
+            }
+        })
+        .try_flatten()
+        .boxed()
     }
 
-    async fn update_secret(
-        &mut self,
-        secret_name: &'a str,
-        secret_version: &'a str,
-        attributes: Map<String, Value>,
-    ) -> Result<(), KeyVaultError> {
-        let uri = Url::parse_with_params(
-            &format!("{}/secrets/{}/{}", self.keyvault_endpoint, secret_name, secret_version),
-            &[("api-version", API_VERSION)],
+    /// Builds the URI of the first page of a listing, or of the page a continuation token points to.
+    fn listing_uri(
+        &self,
+        uri: String,
+        page_size: usize,
+        continuation_token: Option<&ContinuationToken>,
+    ) -> Result<Url, KeyVaultError> {
+        if let Some(token) = continuation_token {
+            return self.continuation_uri(&uri, token);
+        }
+        if page_size == 0 || page_size > DEFAULT_MAX_RESULTS {
+            return Err(KeyVaultError::GeneralError(format!(
+                "Page size must be between 1 and {}, got {}",
+                DEFAULT_MAX_RESULTS, page_size
+            )));
+        }
+        Ok(Url::parse_with_params(
+            &uri,
+            &[("api-version", API_VERSION), ("maxresults", &page_size.to_string())],
         )
-        .unwrap();
+        .unwrap())
+    }
 
-        let mut request_body = Map::new();
-        request_body.insert("attributes".to_owned(), Value::Object(attributes));
+    /// Checks that a continuation token, or the `nextLink` of a page, points at the next page of the listing at `uri`.
+    /// The request carries the bearer token, so a token is never followed to another host, and a token of
+    /// another listing is rejected rather than returning the wrong items.
+    fn continuation_uri(&self, uri: &str, token: &ContinuationToken) -> Result<Url, KeyVaultError> {
+        let listing = Url::parse(uri).unwrap();
+        match Url::parse(token.as_str()) {
+            Ok(next)
+                if next.scheme() == listing.scheme()
+                    && next.host() == listing.host()
+                    && next.port_or_known_default() == listing.port_or_known_default()
+                    && next.path().trim_end_matches('/') == listing.path().trim_end_matches('/') =>
+            {
+                Ok(next)
+            }
+            _ => Err(KeyVaultError::GeneralError(format!(
+                "Continuation token does not belong to the listing at '{}'",
+                uri
+            ))),
+        }
+    }
 
-        self.patch_authed(uri.to_string(), Value::Object(request_body).to_string())
-            .await?;
+    async fn get_secret_identifiers_page(
+        &mut self,
+        uri: Url,
+    ) -> Result<KeyVaultPage<KeyVaultSecretBaseIdentifier>, KeyVaultError> {
+        let resp_body = self.get_authed(uri.to_string()).await?;
+        let response = serde_json::from_str::<KeyVaultGetSecretsResponse>(&resp_body)
+            .with_context(|| format!("Failed to parse response from Key Vault: {}", resp_body))?;
 
-        Ok(())
+        let page = response
+            .value
+            .into_iter()
+            .map(|s| {
+                let identifier = KeyVaultIdentifier::parse(&s.id)?;
+                Ok(KeyVaultSecretBaseIdentifier {
+                    name: identifier.name().to_owned(),
+                    version: identifier.version().clone(),
+                    id: s.id,
+                    content_type: s.content_type,
+                    tags: s.tags,
+                    enabled: s.attributes.enabled,
+                    time_not_before: s.attributes.nbf,
+                    time_expires: s.attributes.exp,
+                    time_created: s.attributes.created,
+                    time_updated: s.attributes.updated,
+                })
+            })
+            .collect::<Result<Vec<_>, KeyVaultError>>()?;
+        Ok(KeyVaultPage::new(page, response.next_link))
     }
 
-    /// Restores a backed up secret and all its versions.
-    /// This operation requires the secrets/restore permission.
+    /// Sets the value of a secret in the Key Vault.
     ///
     /// # Example
     ///
@@ -500,23 +866,517 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.restore_secret(&"KUF6dXJlS2V5VmF1bHRTZWNyZXRCYWNrdXBWMS5taW").await.unwrap();
+    ///     client.set_secret(&"SECRET_NAME", &"NEW_VALUE").await.unwrap();
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn restore_secret(&mut self, backup_blob: &'a str) -> Result<(), KeyVaultError> {
-        let uri = Url::parse_with_params(
-            &format!("{}/secrets/restore", self.keyvault_endpoint),
-            &[("api-version", API_VERSION)],
-        )
-        .unwrap();
-
-        let mut request_body = Map::new();
-        request_body.insert("value".to_owned(), Value::String(backup_blob.to_owned()));
+    pub async fn set_secret(&mut self, secret_name: &str, new_secret_value: &str) -> Result<(), KeyVaultError> {
+        self.set_secret_with_content_type(secret_name, new_secret_value, None)
+            .await
+    }
 
-        self.post_authed(uri.to_string(), Some(Value::Object(request_body).to_string()))
-            .await?;
+    /// Sets the value of a secret in the Key Vault from a [`SecretValue`](SecretValue), such as one read from
+    /// another secret, without exposing it to the caller.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::{KeyVaultClient, SecretValue};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let value = SecretValue::from(std::env::var("NEW_VALUE").unwrap());
+    ///     client.set_secret_value(&"SECRET_NAME", &value).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_value(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &SecretValue,
+    ) -> Result<(), KeyVaultError> {
+        self.put_secret(secret_name, new_secret_value.expose(), &SecretProperties::default())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sets the value of a secret in the Key Vault, unless its latest version already holds that value,
+    /// so that setting the same value over and over does not pile up versions.
+    /// Returns whether a new version was created.
+    ///
+    /// The current value is read to compare it, so this operation requires the secrets/get and
+    /// secrets/set permissions. Comparing against a digest stored in a tag instead would let anyone
+    /// with the secrets/list permission guess low-entropy values offline.
+    /// A disabled latest version can not be read, and counts as changed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     if client.set_secret_if_changed(&"SECRET_NAME", &"NEW_VALUE").await.unwrap() {
+    ///         println!("Created a new version");
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_if_changed(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &str,
+    ) -> Result<bool, KeyVaultError> {
+        match self.find_secret(secret_name, "").await {
+            Ok(Some(current)) if current.value().expose() == new_secret_value => return Ok(false),
+            Ok(_) | Err(KeyVaultError::SecretDisabled(_)) => {}
+            Err(e) => return Err(e),
+        }
+        self.set_secret(secret_name, new_secret_value).await?;
+        Ok(true)
+    }
+
+    /// Gets a version of a secret, or the latest version if `secret_version_name` is empty.
+    /// Returns `None` if the secret or version does not exist.
+    pub(crate) async fn find_secret(
+        &mut self,
+        secret_name: &str,
+        secret_version_name: &str,
+    ) -> Result<Option<KeyVaultSecret>, KeyVaultError> {
+        match self.get_secret_with_version(secret_name, secret_version_name).await {
+            Ok(secret) => Ok(Some(secret)),
+            Err(KeyVaultError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets the value of a secret in the Key Vault, tagging it with a content type such as `application/json`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.set_secret_with_content_type(&"SECRET_NAME", &"NEW_VALUE", Some("text/plain")).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_with_content_type(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &str,
+        content_type: Option<&str>,
+    ) -> Result<(), KeyVaultError> {
+        let properties = SecretProperties {
+            content_type: content_type.map(str::to_owned),
+            ..SecretProperties::default()
+        };
+        self.put_secret(secret_name, new_secret_value, &properties).await?;
+
+        Ok(())
+    }
+
+    /// Sets the value of a secret, returning the newly created version.
+    pub(crate) async fn set_secret_version(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &str,
+        content_type: Option<&str>,
+    ) -> Result<KeyVaultSecret, KeyVaultError> {
+        let properties = SecretProperties {
+            content_type: content_type.map(str::to_owned),
+            ..SecretProperties::default()
+        };
+        self.set_secret_with_properties(secret_name, new_secret_value, &properties)
+            .await
+    }
+
+    /// Sets the value of a secret in the Key Vault along with its content type, tags and attributes,
+    /// returning the newly created version.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::secret::SecretProperties;
+    /// use chrono::{Duration, Utc};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let properties = SecretProperties::default()
+    ///         .with_tag("owner", "team-a")
+    ///         .with_expires(Utc::now() + Duration::days(90));
+    ///     let secret = client
+    ///         .set_secret_with_properties(&"SECRET_NAME", &"NEW_VALUE", &properties)
+    ///         .await
+    ///         .unwrap();
+    ///     dbg!(secret.version());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_with_properties(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &str,
+        properties: &SecretProperties,
+    ) -> Result<KeyVaultSecret, KeyVaultError> {
+        let resp_body = self.put_secret(secret_name, new_secret_value, properties).await?;
+        serde_json::from_str::<KeyVaultGetSecretResponse>(&resp_body)
+            .with_context(|| {
+                format!(
+                    "Failed to parse response from Key Vault when setting secret {}",
+                    secret_name
+                )
+            })?
+            .into_secret()
+    }
+
+    /// Sets the value of a secret in the Key Vault from a [`SecretValue`](SecretValue), along with its content
+    /// type, tags and attributes, returning the newly created version.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::{KeyVaultClient, SecretValue};
+    /// use azure_sdk_keyvault::secret::SecretProperties;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let value = SecretValue::from(std::env::var("NEW_VALUE").unwrap());
+    ///     let properties = SecretProperties::default().with_content_type("text/plain");
+    ///     let secret = client
+    ///         .set_secret_value_with_properties(&"SECRET_NAME", &value, &properties)
+    ///         .await
+    ///         .unwrap();
+    ///     dbg!(secret.version());
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn set_secret_value_with_properties(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &SecretValue,
+        properties: &SecretProperties,
+    ) -> Result<KeyVaultSecret, KeyVaultError> {
+        self.set_secret_with_properties(secret_name, new_secret_value.expose(), properties)
+            .await
+    }
+
+    /// Sets the value of a secret, returning the raw response body (which holds the secret value).
+    async fn put_secret(
+        &mut self,
+        secret_name: &str,
+        new_secret_value: &str,
+        properties: &SecretProperties,
+    ) -> Result<Zeroizing<String>, KeyVaultError> {
+        validate_secret_name(secret_name)?;
+        validate_tags(secret_name, &properties.tags)?;
+        validate_secret_value(secret_name, new_secret_value)?;
+        let uri = Url::parse_with_params(
+            &format!("{}/secrets/{}", self.keyvault_endpoint, secret_name),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+
+        // JSON escaping takes at most six bytes per byte, so the buffer is sized up front and never reallocated,
+        // which would leave copies of the value behind. The buffer is then moved into the request without
+        // being copied, but the HTTP client frees it without zeroing it.
+        let escaped_length = new_secret_value.len()
+            + properties.content_type.as_deref().map_or(0, str::len)
+            + properties
+                .tags
+                .iter()
+                .map(|(name, value)| name.len() + value.len())
+                .sum::<usize>();
+        let mut request_body = Zeroizing::new(Vec::with_capacity(6 * escaped_length + 256));
+        serde_json::to_writer(
+            &mut *request_body,
+            &KeyVaultSetSecretRequest {
+                value: new_secret_value,
+                content_type: properties.content_type.as_deref(),
+                tags: Some(&properties.tags).filter(|tags| !tags.is_empty()),
+                attributes: KeyVaultSetSecretRequestAttributes {
+                    enabled: properties.enabled,
+                    nbf: properties.not_before,
+                    exp: properties.expires,
+                },
+            },
+        )
+        .unwrap();
+
+        let request_body = std::mem::take(&mut *request_body);
+        Ok(Zeroizing::new(self.put_authed(uri.to_string(), request_body).await?))
+    }
+
+    /// Updates whether a secret version is enabled or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the secret
+    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
+    /// * `enabled` - New `enabled` value of the secret
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.update_secret_enabled(&"SECRET_NAME", &"", true).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn update_secret_enabled(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        enabled: bool,
+    ) -> Result<(), KeyVaultError> {
+        let mut attributes = Map::new();
+        attributes.insert("enabled".to_owned(), Value::Bool(enabled));
+
+        self.update_secret(secret_name, secret_version, attributes).await?;
+
+        Ok(())
+    }
+
+    /// Updates the [`RecoveryLevel`](RecoveryLevel) of a secret version.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the secret
+    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
+    /// * `recovery_level` - New `RecoveryLevel`(RecoveryLevel) value of the secret
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::{KeyVaultClient, RecoveryLevel};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.update_secret_recovery_level(&"SECRET_NAME", &"", RecoveryLevel::Purgeable).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn update_secret_recovery_level(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        recovery_level: RecoveryLevel,
+    ) -> Result<(), KeyVaultError> {
+        let mut attributes = Map::new();
+        attributes.insert("enabled".to_owned(), Value::String(recovery_level.to_string()));
+
+        self.update_secret(secret_name, secret_version, attributes).await?;
+
+        Ok(())
+    }
+
+    /// Updates the expiration time of a secret version.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the secret
+    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
+    /// * `expiration_time - New expiration time of the secret
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::{KeyVaultClient, RecoveryLevel};
+    /// use tokio::runtime::Runtime;
+    /// use chrono::{Utc, Duration};
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.update_secret_expiration_time(&"SECRET_NAME", &"", Utc::now() + Duration::days(14)).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn update_secret_expiration_time(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        expiration_time: DateTime<Utc>,
+    ) -> Result<(), KeyVaultError> {
+        let mut attributes = Map::new();
+        attributes.insert(
+            "exp".to_owned(),
+            Value::Number(serde_json::Number::from(expiration_time.timestamp())),
+        );
+
+        self.update_secret(secret_name, secret_version, attributes).await?;
+
+        Ok(())
+    }
+
+    /// Replaces the tags of a secret version.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the secret
+    /// * `secret_version` - Version of the secret. Use an empty string for the latest version
+    /// * `tags` - New tags of the secret version
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use std::collections::HashMap;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let mut tags = HashMap::new();
+    ///     tags.insert("owner".to_owned(), "team-a".to_owned());
+    ///     client.update_secret_tags(&"SECRET_NAME", &"", &tags).await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn update_secret_tags(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        tags: &HashMap<String, String>,
+    ) -> Result<(), KeyVaultError> {
+        validate_tags(secret_name, tags)?;
+        let mut request_body = Map::new();
+        request_body.insert("tags".to_owned(), serde_json::to_value(tags).unwrap());
+
+        self.patch_secret(secret_name, secret_version, request_body).await
+    }
+
+    async fn update_secret(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        attributes: Map<String, Value>,
+    ) -> Result<(), KeyVaultError> {
+        let mut request_body = Map::new();
+        request_body.insert("attributes".to_owned(), Value::Object(attributes));
+
+        self.patch_secret(secret_name, secret_version, request_body).await
+    }
+
+    pub(crate) async fn patch_secret(
+        &mut self,
+        secret_name: &str,
+        secret_version: &str,
+        request_body: Map<String, Value>,
+    ) -> Result<(), KeyVaultError> {
+        validate_secret_version(secret_name, secret_version)?;
+        let uri = Url::parse_with_params(
+            &format!("{}/secrets/{}/{}", self.keyvault_endpoint, secret_name, secret_version),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+
+        self.patch_authed(uri.to_string(), Value::Object(request_body).to_string())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Restores a backed up secret and all its versions.
+    /// This operation requires the secrets/restore permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.restore_secret(&"KUF6dXJlS2V5VmF1bHRTZWNyZXRCYWNrdXBWMS5taW").await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn restore_secret(&mut self, backup_blob: &str) -> Result<(), KeyVaultError> {
+        let uri = Url::parse_with_params(
+            &format!("{}/secrets/restore", self.keyvault_endpoint),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+
+        let mut request_body = Map::new();
+        request_body.insert("value".to_owned(), Value::String(backup_blob.to_owned()));
+
+        self.post_authed(uri.to_string(), Some(Value::Object(request_body).to_string()))
+            .await?;
 
         Ok(())
     }
@@ -542,7 +1402,8 @@ impl<'a> KeyVaultClient<'a> {
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn backup_secret(&mut self, secret_name: &'a str) -> Result<KeyVaultSecretBackupBlob, KeyVaultError> {
+    pub async fn backup_secret(&mut self, secret_name: &str) -> Result<KeyVaultSecretBackupBlob, KeyVaultError> {
+        validate_secret_name(secret_name)?;
         let uri = Url::parse_with_params(
             &format!("{}/secrets/{}/backup", self.keyvault_endpoint, secret_name),
             &[("api-version", API_VERSION)],
@@ -553,8 +1414,7 @@ impl<'a> KeyVaultClient<'a> {
         let backup_blob = serde_json::from_str::<KeyVaultSecretBackupResponseRaw>(&response).with_context(|| {
             format!(
                 "Failed to parse response from Key Vault when backing up secret {}: {}",
-                secret_name,
-                response.to_string()
+                secret_name, response
             )
         })?;
 
@@ -564,6 +1424,8 @@ impl<'a> KeyVaultClient<'a> {
     }
 
     /// Deletes a secret in the Key Vault.
+    /// Deletion completes asynchronously - the returned poller can be awaited until the secret shows up
+    /// as deleted in vaults with soft-delete enabled, or until it is gone in other vaults.
     ///
     /// # Arguments
     ///
@@ -582,21 +1444,113 @@ impl<'a> KeyVaultClient<'a> {
     ///     &"TENANT_ID",
     ///     &"KEYVAULT_NAME",
     ///     );
-    ///     client.delete_secret(&"SECRET_NAME").await.unwrap();
+    ///     client.delete_secret(&"SECRET_NAME").await.unwrap().wait().await.unwrap();
     /// }
     ///
     /// Runtime::new().unwrap().block_on(example());
     /// ```
-    pub async fn delete_secret(&mut self, secret_name: &'a str) -> Result<(), KeyVaultError> {
+    pub async fn delete_secret<'c>(
+        &'c mut self,
+        secret_name: &str,
+    ) -> Result<SecretOperationPoller<'c, 'a>, KeyVaultError> {
+        validate_secret_name(secret_name)?;
         let uri = Url::parse_with_params(
             &format!("{}/secrets/{}", self.keyvault_endpoint, secret_name),
             &[("api-version", API_VERSION)],
         )
         .unwrap();
 
+        let resp_body = self.delete_authed(uri.to_string()).await?;
+        // Only vaults with soft-delete enabled keep the deleted secret, and return where to recover it from.
+        let soft_delete = serde_json::from_str::<KeyVaultDeleteSecretResponseRaw>(&resp_body)
+            .map(|response| response.recovery_id.is_some())
+            .unwrap_or(false);
+
+        Ok(SecretOperationPoller::new(self, SecretOperation::Delete, secret_name).with_soft_delete(soft_delete))
+    }
+
+    /// Recovers a deleted secret back into the Key Vault, with all its versions.
+    /// This operation requires the secrets/recover permission, and is only available in vaults with soft-delete enabled.
+    /// The returned poller can be awaited until the secret is available again, which requires the secrets/list permission.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the deleted secret
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.recover_deleted_secret(&"SECRET_NAME").await.unwrap().wait().await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn recover_deleted_secret<'c>(
+        &'c mut self,
+        secret_name: &str,
+    ) -> Result<SecretOperationPoller<'c, 'a>, KeyVaultError> {
+        validate_secret_name(secret_name)?;
+        let uri = Url::parse_with_params(
+            &format!("{}/deletedsecrets/{}/recover", self.keyvault_endpoint, secret_name),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+
+        self.post_authed(uri.to_string(), None).await?;
+
+        Ok(SecretOperationPoller::new(self, SecretOperation::Recover, secret_name))
+    }
+
+    /// Permanently deletes a deleted secret, without the possibility of recovery.
+    /// This operation requires the secrets/purge permission, and is only available in vaults with soft-delete enabled.
+    /// The returned poller can be awaited until the purge has completed, after which the secret name can be reused.
+    ///
+    /// # Arguments
+    ///
+    /// * `secret_name` - Name of the deleted secret
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     client.purge_deleted_secret(&"SECRET_NAME").await.unwrap().wait().await.unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn purge_deleted_secret<'c>(
+        &'c mut self,
+        secret_name: &str,
+    ) -> Result<SecretOperationPoller<'c, 'a>, KeyVaultError> {
+        validate_secret_name(secret_name)?;
+        let uri = Url::parse_with_params(
+            &format!("{}/deletedsecrets/{}", self.keyvault_endpoint, secret_name),
+            &[("api-version", API_VERSION)],
+        )
+        .unwrap();
+
         self.delete_authed(uri.to_string()).await?;
 
-        Ok(())
+        Ok(SecretOperationPoller::new(self, SecretOperation::Purge, secret_name))
     }
 }
 
@@ -605,9 +1559,9 @@ impl<'a> KeyVaultClient<'a> {
 mod tests {
     use super::*;
 
+    use crate::validation::{SecretValidationError, MAX_SECRET_VALUE_SIZE};
     use chrono::{Duration, Utc};
     use mockito::{mock, Matcher};
-    use oauth2::AccessToken;
     use serde_json::json;
 
     fn diff(first: DateTime<Utc>, second: DateTime<Utc>) -> Duration {
@@ -618,21 +1572,6 @@ mod tests {
         }
     }
 
-    macro_rules! mock_client {
-        ($keyvault_name:expr) => {{
-            let mut client = KeyVaultClient::with_aad_token(
-                &"",
-                &"",
-                &"TENANT_ID",
-                $keyvault_name,
-                AccessToken::new("TOKEN".to_owned()),
-                Utc::now() + Duration::days(14),
-            );
-            client.keyvault_endpoint = mockito::server_url();
-            client
-        }};
-    }
-
     #[tokio::test]
     async fn get_secret() {
         let time_created = Utc::now() - Duration::days(7);
@@ -658,14 +1597,17 @@ mod tests {
 
         let mut client = mock_client!(&"test-keyvault");
 
-        let secret: KeyVaultSecret = client.get_secret(&"test-secret").await.unwrap();
+        let secret: KeyVaultSecret = client.get_secret("test-secret").await.unwrap();
 
-        assert_eq!("secret-value", secret.value());
+        assert_eq!("secret-value", secret.value().expose());
+        assert!(!format!("{:?}", secret).contains("secret-value"));
         assert_eq!(
             "https://test-keyvault.vault.azure.net/secrets/test-secret/4387e9f3d6e14c459867679a90fd0f79",
             secret.id()
         );
-        assert_eq!(true, *secret.enabled());
+        assert_eq!("test-secret", secret.name());
+        assert_eq!("4387e9f3d6e14c459867679a90fd0f79", secret.version());
+        assert!(*secret.enabled());
         assert!(diff(time_created, *secret.time_created()) < Duration::seconds(1));
         assert!(diff(time_updated, *secret.time_updated()) < Duration::seconds(1));
     }
@@ -693,14 +1635,14 @@ mod tests {
                             "updated": time_updated_1.timestamp(),
                         }
                     }],
-                    "nextLink": format!("{}/secrets/text-secret/versions?api-version={}&maxresults=1&$skiptoken=SKIP_TOKEN_MOCK", mockito::server_url().to_string(), API_VERSION)
+                    "nextLink": format!("{}/secrets/test-secret/versions?api-version={}&maxresults=1&$skiptoken=SKIP_TOKEN_MOCK", mockito::server_url().to_string(), API_VERSION)
                 })
                 .to_string(),
             )
             .with_status(200)
             .create();
 
-        let _m2 = mock("GET", "/secrets/text-secret/versions")
+        let _m2 = mock("GET", "/secrets/test-secret/versions")
             .match_query(Matcher::AllOf(vec![
                 Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
                 Matcher::UrlEncoded("maxresults".into(), "1".into()),
@@ -726,13 +1668,15 @@ mod tests {
 
         let mut client = mock_client!(&"test-keyvault");
 
-        let secret_versions = client.get_secret_versions(&"test-secret").await.unwrap();
+        let secret_versions = client.get_secret_versions("test-secret").await.unwrap();
 
         let secret_1 = &secret_versions[0];
         assert_eq!(
             "https://test-keyvault.vault.azure.net/secrets/test-secret/VERSION_1",
             secret_1.id()
         );
+        assert_eq!("test-secret", secret_1.name());
+        assert_eq!(Some("VERSION_1"), secret_1.version().as_deref());
         assert!(diff(time_created_1, *secret_1.time_created()) < Duration::seconds(1));
         assert!(diff(time_updated_1, *secret_1.time_updated()) < Duration::seconds(1));
 
@@ -744,4 +1688,398 @@ mod tests {
         assert!(diff(time_created_2, *secret_2.time_created()) < Duration::seconds(1));
         assert!(diff(time_updated_2, *secret_2.time_updated()) < Duration::seconds(1));
     }
+
+    #[tokio::test]
+    async fn list_secrets_stream_stops_early() {
+        let time_created = Utc::now() - Duration::days(7);
+        let time_updated = Utc::now();
+
+        let _m1 = mock("GET", "/stream-early/secrets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "2".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "value": [{
+                        "id": "https://test-keyvault.vault.azure.net/secrets/secret-1",
+                        "attributes": {
+                            "enabled": true,
+                            "created": time_created.timestamp(),
+                            "updated": time_updated.timestamp(),
+                        }
+                    }, {
+                        "id": "https://test-keyvault.vault.azure.net/secrets/secret-2",
+                        "attributes": {
+                            "enabled": false,
+                            "created": time_created.timestamp(),
+                            "updated": time_updated.timestamp(),
+                        }
+                    }],
+                    "nextLink": format!("{}/stream-early/secrets?api-version={}&maxresults=1&$skiptoken=SKIP_TOKEN_MOCK", mockito::server_url(), API_VERSION)
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let m2 = mock("GET", "/stream-early/secrets")
+            .match_query(Matcher::UrlEncoded("$skiptoken".into(), "SKIP_TOKEN_MOCK".into()))
+            .with_status(200)
+            .expect(0)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/stream-early", mockito::server_url());
+
+        let secrets: Vec<KeyVaultSecretBaseIdentifier> =
+            client.list_secrets_stream(2).take(2).try_collect().await.unwrap();
+
+        assert_eq!(2, secrets.len());
+        assert_eq!("secret-1", secrets[0].name());
+        assert_eq!(None, *secrets[0].version());
+        assert!(!*secrets[1].enabled());
+        m2.assert();
+    }
+
+    #[tokio::test]
+    async fn list_deleted_secrets_page_resumes_from_token() {
+        let time_created = Utc::now() - Duration::days(7);
+        let time_updated = Utc::now() - Duration::days(1);
+        let time_deleted = Utc::now();
+        let time_scheduled_purge = Utc::now() + Duration::days(90);
+
+        let _m = mock("GET", "/deletedsecrets")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("api-version".into(), API_VERSION.into()),
+                Matcher::UrlEncoded("maxresults".into(), "1".into()),
+                Matcher::UrlEncoded("$skiptoken".into(), "SKIP_TOKEN_MOCK".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "value": [{
+                        "id": "https://test-keyvault.vault.azure.net/secrets/deleted-secret",
+                        "recoveryId": "https://test-keyvault.vault.azure.net/deletedsecrets/deleted-secret",
+                        "deletedDate": time_deleted.timestamp(),
+                        "scheduledPurgeDate": time_scheduled_purge.timestamp(),
+                        "attributes": {
+                            "enabled": true,
+                            "created": time_created.timestamp(),
+                            "updated": time_updated.timestamp(),
+                        }
+                    }],
+                    "nextLink": null
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let token = ContinuationToken::new(format!(
+            "{}/deletedsecrets?api-version={}&maxresults=1&$skiptoken=SKIP_TOKEN_MOCK",
+            mockito::server_url(),
+            API_VERSION
+        ));
+        let page = client.list_deleted_secrets_page(25, Some(&token)).await.unwrap();
+
+        assert!(page.continuation_token().is_none());
+        let deleted_secret = &page.items()[0];
+        assert_eq!("deleted-secret", deleted_secret.name());
+        assert_eq!(
+            "https://test-keyvault.vault.azure.net/deletedsecrets/deleted-secret",
+            deleted_secret.recovery_id()
+        );
+        assert!(diff(time_deleted, *deleted_secret.time_deleted()) < Duration::seconds(1));
+        assert!(diff(time_scheduled_purge, *deleted_secret.time_scheduled_purge()) < Duration::seconds(1));
+    }
+
+    #[tokio::test]
+    async fn list_secrets_page_rejects_foreign_token() {
+        let mut client = mock_client!(&"test-keyvault");
+
+        let token =
+            ContinuationToken::new("https://attacker.example.com/secrets?$skiptoken=SKIP_TOKEN_MOCK".to_owned());
+        let err = client.list_secrets_page(25, Some(&token)).await.unwrap_err();
+
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+
+    #[tokio::test]
+    async fn page_methods_reject_tokens_of_other_listings() {
+        let mut client = mock_client!(&"test-keyvault");
+
+        let token = ContinuationToken::new(format!(
+            "{}/secrets?api-version={}&maxresults=25&$skiptoken=SKIP_TOKEN_MOCK",
+            mockito::server_url(),
+            API_VERSION
+        ));
+        let err = client.list_deleted_secrets_page(25, Some(&token)).await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+        let err = client
+            .get_secret_versions_page("test-secret", 25, Some(&token))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+
+        let token = ContinuationToken::new(format!(
+            "{}/secrets/other-secret/versions?api-version={}&$skiptoken=SKIP_TOKEN_MOCK",
+            mockito::server_url(),
+            API_VERSION
+        ));
+        let err = client
+            .get_secret_versions_page("test-secret", 25, Some(&token))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+
+    #[tokio::test]
+    async fn list_secrets_stream_rejects_malformed_next_link() {
+        let _m = mock("GET", "/malformed-link/secrets")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "value": [], "nextLink": "not a link" }).to_string())
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/malformed-link", mockito::server_url());
+
+        let err = client.list_secrets().await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(_)));
+    }
+
+    #[tokio::test]
+    async fn get_secret_by_id() {
+        let _m = mock("GET", "/secrets/test-secret-by-id/VERSION_1")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "value": "secret-value",
+                    "id": format!("{}/secrets/test-secret-by-id/VERSION_1", mockito::server_url()),
+                    "attributes": {
+                        "enabled": true,
+                        "created": Utc::now().timestamp(),
+                        "updated": Utc::now().timestamp(),
+                        "recoveryLevel": "Recoverable+Purgeable"
+                    }
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let secret = client
+            .get_secret_by_id(&format!(
+                "{}/secrets/test-secret-by-id/VERSION_1",
+                mockito::server_url()
+            ))
+            .await
+            .unwrap();
+        assert_eq!("secret-value", secret.value().expose());
+        assert_eq!("VERSION_1", secret.version());
+
+        let err = client
+            .get_secret_by_id("https://other-keyvault.vault.azure.net/secrets/test-secret-by-id/VERSION_1")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidIdentifier(_)));
+    }
+
+    #[tokio::test]
+    async fn setters_and_updaters_report_errors() {
+        let error_body = json!({
+            "error": { "code": "Forbidden", "message": "The user does not have secrets set permission" }
+        })
+        .to_string();
+        let _m1 = mock("PUT", "/secrets/forbidden-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(&error_body)
+            .with_status(403)
+            .create();
+        let _m2 = mock("PATCH", "/secrets/forbidden-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(&error_body)
+            .with_status(403)
+            .create();
+        let _m3 = mock("POST", "/secrets/restore")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "Forbidden" } }).to_string())
+            .with_status(403)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.set_secret("forbidden-secret", "new-value").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("secrets set permission")));
+        let err = client
+            .update_secret_enabled("forbidden-secret", "", false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("secrets set permission")));
+        // An error without a message is still reported, rather than panicking.
+        let err = client.restore_secret("BACKUP_BLOB").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("Forbidden")));
+    }
+
+    #[tokio::test]
+    async fn get_secret_reports_errors() {
+        let _m1 = mock("GET", "/secrets/missing-secret-get/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }).to_string())
+            .with_status(404)
+            .create();
+        let _m2 = mock("GET", "/secrets/forbidden-secret-get/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "Forbidden", "message": "Access denied" } }).to_string())
+            .with_status(403)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.get_secret("missing-secret-get").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::NotFound(_)));
+        let err = client.get_secret("forbidden-secret-get").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::GeneralError(message) if message.contains("Access denied")));
+    }
+
+    #[tokio::test]
+    async fn set_secret_value() {
+        let value = SecretValue::from("new-value\u{1}\"");
+        let m = mock("PUT", "/secrets/secret-value")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": value.expose() })))
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        client.set_secret_value("secret-value", &value).await.unwrap();
+        m.assert();
+    }
+
+    #[tokio::test]
+    async fn set_secret_if_changed() {
+        let _m1 = mock("GET", "/secrets/unchanged-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "value": "current-value",
+                    "id": "https://test-keyvault.vault.azure.net/secrets/unchanged-secret/VERSION_1",
+                    "attributes": {
+                        "enabled": true,
+                        "created": Utc::now().timestamp(),
+                        "updated": Utc::now().timestamp(),
+                        "recoveryLevel": "Recoverable+Purgeable"
+                    }
+                })
+                .to_string(),
+            )
+            .with_status(200)
+            .create();
+        let _m2 = mock("GET", "/secrets/missing-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "error": { "code": "SecretNotFound", "message": "A secret with (name/id) missing-secret was not found in this key vault." }
+                })
+                .to_string(),
+            )
+            .with_status(404)
+            .create();
+        let _m3 = mock("GET", "/secrets/disabled-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "error": {
+                        "code": "Forbidden",
+                        "message": "Operation get is not allowed on a disabled secret.",
+                        "innererror": { "code": "SecretDisabled" }
+                    }
+                })
+                .to_string(),
+            )
+            .with_status(403)
+            .create();
+        let put_unchanged = mock("PUT", "/secrets/unchanged-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .match_body(Matcher::Json(json!({ "value": "new-value" })))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let put_missing = mock("PUT", "/secrets/missing-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_status(200)
+            .expect(1)
+            .create();
+        let put_disabled = mock("PUT", "/secrets/disabled-secret")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_status(200)
+            .expect(1)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+
+        assert!(!client
+            .set_secret_if_changed("unchanged-secret", "current-value")
+            .await
+            .unwrap());
+        assert!(client
+            .set_secret_if_changed("unchanged-secret", "new-value")
+            .await
+            .unwrap());
+        assert!(client
+            .set_secret_if_changed("missing-secret", "new-value")
+            .await
+            .unwrap());
+        assert!(client
+            .set_secret_if_changed("disabled-secret", "new-value")
+            .await
+            .unwrap());
+        put_unchanged.assert();
+        put_missing.assert();
+        put_disabled.assert();
+    }
+
+    #[tokio::test]
+    async fn invalid_secrets_are_rejected_before_sending() {
+        let mut client = mock_client!(&"test-keyvault");
+
+        let err = client.get_secret_with_version("test-secret/..", "").await.unwrap_err();
+        assert!(matches!(
+            err,
+            KeyVaultError::InvalidSecret {
+                reason: SecretValidationError::InvalidName,
+                ..
+            }
+        ));
+        let err = client.get_secret_versions("test_secret").await.unwrap_err();
+        assert!(matches!(err, KeyVaultError::InvalidSecret { .. }));
+        let err = client
+            .set_secret("test-secret", &"a".repeat(MAX_SECRET_VALUE_SIZE + 1))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            KeyVaultError::InvalidSecret {
+                reason: SecretValidationError::ValueTooLarge(_),
+                ..
+            }
+        ));
+    }
 }
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// The plaintext value of a secret.
+///
+/// The value is redacted from `Debug` and `Display` output, and its memory is zeroed when dropped.
+/// The plaintext is only reachable through [`expose`](SecretValue::expose).
+///
+/// With the `secrecy` feature enabled, it converts to and from `secrecy::SecretString`.
+///
+/// # Example
+///
+/// ```
+/// use azure_sdk_keyvault::SecretValue;
+///
+/// let value = SecretValue::from("hunter2");
+/// assert_eq!("[REDACTED]", format!("{}", value));
+/// assert_eq!("hunter2", value.expose());
+/// ```
+#[derive(Clone, Default)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn new(value: String) -> Self {
+        Self(value)
+    }
+
+    /// Returns the plaintext value of the secret.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretValue {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl From<&str> for SecretValue {
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+impl Drop for SecretValue {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretValue([REDACTED])")
+    }
+}
+
+impl fmt::Display for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(SecretValue)
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<secrecy::SecretString> for SecretValue {
+    fn from(value: secrecy::SecretString) -> Self {
+        use secrecy::ExposeSecret;
+        Self(value.expose_secret().to_owned())
+    }
+}
+
+#[cfg(feature = "secrecy")]
+impl From<SecretValue> for secrecy::SecretString {
+    fn from(value: SecretValue) -> Self {
+        secrecy::SecretString::new(value.expose().to_owned())
+    }
+}
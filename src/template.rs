@@ -0,0 +1,295 @@
+use crate::report::describe_error;
+use crate::{KeyVaultClient, KeyVaultError, SecretValue};
+use futures::stream::{self, StreamExt};
+use getset::Getters;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const PLACEHOLDER_START: &str = "${kv:";
+
+/// Options for [`render_template`](KeyVaultClient::render_template).
+#[derive(Debug, Clone)]
+pub struct TemplateOptions {
+    strict: bool,
+    concurrency: usize,
+}
+
+impl Default for TemplateOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            concurrency: 8,
+        }
+    }
+}
+
+impl TemplateOptions {
+    /// In strict mode, the default, rendering fails if any placeholder can not be resolved.
+    /// Otherwise unresolved placeholders are left in the text as they are, and reported.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// How many secrets are fetched at a time.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+}
+
+/// A placeholder which could not be substituted.
+#[derive(Debug, Clone, PartialEq, Eq, Getters, Serialize)]
+#[getset(get = "pub")]
+pub struct UnresolvedPlaceholder {
+    /// The placeholder as written in the template, such as `${kv:secret-name}`.
+    placeholder: String,
+    reason: String,
+}
+
+/// A template with its placeholders substituted.
+#[derive(Debug, Getters)]
+#[getset(get = "pub")]
+pub struct RenderedTemplate {
+    /// The rendered text, which holds secret values.
+    text: SecretValue,
+    /// Placeholders left as they are, in lenient mode.
+    unresolved: Vec<UnresolvedPlaceholder>,
+}
+
+enum Segment<'t> {
+    Text(&'t str),
+    Placeholder {
+        raw: &'t str,
+        secret: Result<(&'t str, &'t str), &'static str>,
+    },
+}
+
+/// Splits a template into text and placeholders. `$${kv:` escapes a placeholder, and is rendered as `${kv:`.
+fn parse(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        if rest[..start].ends_with('$') {
+            segments.push(Segment::Text(&rest[..start - 1]));
+            segments.push(Segment::Text(PLACEHOLDER_START));
+            rest = &rest[start + PLACEHOLDER_START.len()..];
+            continue;
+        }
+        segments.push(Segment::Text(&rest[..start]));
+        let placeholder = &rest[start..];
+        let end = match placeholder.find('}') {
+            Some(end) => end,
+            None => {
+                segments.push(Segment::Placeholder {
+                    raw: placeholder,
+                    secret: Err("the placeholder is not terminated"),
+                });
+                return segments;
+            }
+        };
+        let raw = &placeholder[..=end];
+        let reference = &raw[PLACEHOLDER_START.len()..raw.len() - 1];
+        let mut parts = reference.splitn(2, '@');
+        let name = parts.next().unwrap_or_default();
+        let version = parts.next().unwrap_or_default();
+        segments.push(Segment::Placeholder {
+            raw,
+            secret: if name.is_empty() {
+                Err("the placeholder has no secret name")
+            } else {
+                Ok((name, version))
+            },
+        });
+        rest = &placeholder[end + 1..];
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Renders a template, substituting `${kv:secret-name}` and `${kv:secret-name@version}` placeholders
+    /// with the values of the secrets they name. Each distinct secret is fetched once.
+    /// Write `$${kv:` to get a literal `${kv:` in the rendered text.
+    ///
+    /// In strict mode, every placeholder which could not be resolved is reported together in
+    /// [`KeyVaultError::UnresolvedPlaceholders`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::template::TemplateOptions;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let template = std::fs::read_to_string("appsettings.template.json").unwrap();
+    ///     let rendered = client.render_template(&template, &TemplateOptions::default()).await.unwrap();
+    ///     std::fs::write("appsettings.json", rendered.text().expose()).unwrap();
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub async fn render_template(
+        &mut self,
+        template: &str,
+        options: &TemplateOptions,
+    ) -> Result<RenderedTemplate, KeyVaultError> {
+        let segments = parse(template);
+        let mut secrets = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Placeholder { secret: Ok(secret), .. } => Some(*secret),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        secrets.sort_unstable();
+        secrets.dedup();
+
+        let mut values = HashMap::new();
+        if !secrets.is_empty() {
+            // Every request below goes through a copy of the client, which shares the token refreshed here.
+            self.refresh_token().await?;
+            let client = &*self;
+            values = stream::iter(secrets)
+                .map(|(name, version)| async move {
+                    let value = client
+                        .clone()
+                        .get_secret_with_version(name, version)
+                        .await
+                        .map(|secret| secret.value().clone());
+                    ((name, version), value)
+                })
+                .buffer_unordered(options.concurrency.max(1))
+                .collect::<HashMap<_, _>>()
+                .await;
+        }
+
+        // Sized up front, so that growing the buffer never leaves copies of secret values behind.
+        let length = segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text_segment) => text_segment.len(),
+                Segment::Placeholder { raw, secret } => {
+                    match secret.ok().and_then(|secret| values[&secret].as_ref().ok()) {
+                        Some(value) => value.expose().len(),
+                        None => raw.len(),
+                    }
+                }
+            })
+            .sum();
+        let mut text = String::with_capacity(length);
+        let mut unresolved = Vec::new();
+        for segment in &segments {
+            let (raw, secret) = match segment {
+                Segment::Text(text_segment) => {
+                    text.push_str(text_segment);
+                    continue;
+                }
+                Segment::Placeholder { raw, secret } => (raw, secret),
+            };
+            let reason = match secret {
+                Ok(secret) => match &values[secret] {
+                    Ok(value) => {
+                        text.push_str(value.expose());
+                        continue;
+                    }
+                    Err(e) => describe_error(e),
+                },
+                Err(reason) => (*reason).to_owned(),
+            };
+            text.push_str(raw);
+            unresolved.push(UnresolvedPlaceholder {
+                placeholder: (*raw).to_owned(),
+                reason,
+            });
+        }
+        let text = SecretValue::new(text);
+
+        if options.strict && !unresolved.is_empty() {
+            return Err(KeyVaultError::UnresolvedPlaceholders {
+                placeholders: unresolved,
+            });
+        }
+        Ok(RenderedTemplate { text, unresolved })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+    use crate::test_utils::secret_bundle;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+
+    fn mock_secret(path: &str, value: &str) -> mockito::Mock {
+        mock("GET", format!("/template/secrets/{}", path).as_str())
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(secret_bundle("db-password", "VERSION_1", value).to_string())
+            .with_status(200)
+            .expect(2)
+            .create()
+    }
+
+    #[tokio::test]
+    async fn render_template() {
+        let latest = mock_secret("db-password/", "latest-password");
+        let versioned = mock_secret("db-password/VERSION_1", "old-password");
+        let _missing = mock("GET", "/template/secrets/missing-secret/")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "error": { "code": "SecretNotFound", "message": "Secret not found" } }).to_string())
+            .with_status(404)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/template", mockito::server_url());
+
+        let template = "password=${kv:db-password}\n\
+                        again=${kv:db-password}\n\
+                        old=${kv:db-password@VERSION_1}\n\
+                        escaped=$${kv:db-password}\n\
+                        missing=${kv:missing-secret}\n\
+                        empty=${kv:}";
+        let err = client
+            .render_template(template, &TemplateOptions::default())
+            .await
+            .unwrap_err();
+        match err {
+            KeyVaultError::UnresolvedPlaceholders { placeholders } => {
+                let placeholders = placeholders
+                    .iter()
+                    .map(|p| p.placeholder().as_str())
+                    .collect::<Vec<_>>();
+                assert_eq!(vec!["${kv:missing-secret}", "${kv:}"], placeholders);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let rendered = client
+            .render_template(template, &TemplateOptions::default().with_strict(false))
+            .await
+            .unwrap();
+        assert_eq!(
+            "password=latest-password\n\
+             again=latest-password\n\
+             old=old-password\n\
+             escaped=${kv:db-password}\n\
+             missing=${kv:missing-secret}\n\
+             empty=${kv:}",
+            rendered.text().expose()
+        );
+        assert_eq!(2, rendered.unresolved().len());
+        latest.assert();
+        versioned.assert();
+    }
+}
@@ -0,0 +1,63 @@
+/// Builds a `KeyVaultClient` with a pre-issued token, pointed at the mockito server.
+macro_rules! mock_client {
+    ($keyvault_name:expr) => {{
+        let mut client = $crate::KeyVaultClient::with_aad_token(
+            &"",
+            &"",
+            &"TENANT_ID",
+            $keyvault_name,
+            oauth2::AccessToken::new("TOKEN".to_owned()),
+            chrono::Utc::now() + chrono::Duration::days(14),
+        );
+        client.keyvault_endpoint = mockito::server_url();
+        client
+    }};
+}
+
+/// Mocks a Key Vault GET request answered with `body`.
+pub(crate) fn mock_get(path: &str, body: serde_json::Value) -> mockito::Mock {
+    get(path, body).create()
+}
+
+/// Mocks a Key Vault GET request answered with `body`, which is expected to be sent `hits` times.
+pub(crate) fn mock_get_expecting(path: &str, body: serde_json::Value, hits: usize) -> mockito::Mock {
+    get(path, body).expect(hits).create()
+}
+
+fn get(path: &str, body: serde_json::Value) -> mockito::Mock {
+    mockito::mock("GET", path)
+        .match_query(mockito::Matcher::Regex(format!(
+            "api-version={}",
+            crate::client::API_VERSION
+        )))
+        .with_header("content-type", "application/json")
+        .with_body(body.to_string())
+        .with_status(200)
+}
+
+/// The body the Key Vault answers with when getting or setting a version of a secret.
+/// Tests needing more fields, such as a content type or tags, add them to the returned JSON.
+pub(crate) fn secret_bundle(secret_name: &str, version: &str, value: &str) -> serde_json::Value {
+    serde_json::json!({
+        "value": value,
+        "id": format!("https://test-keyvault.vault.azure.net/secrets/{}/{}", secret_name, version),
+        "attributes": {
+            "enabled": true,
+            "created": 1_600_000_000,
+            "updated": 1_600_000_000,
+            "recoveryLevel": "Recoverable+Purgeable"
+        }
+    })
+}
+
+/// Like [`secret_bundle`], for a secret with a content type.
+pub(crate) fn typed_secret_bundle(
+    secret_name: &str,
+    version: &str,
+    value: &str,
+    content_type: &str,
+) -> serde_json::Value {
+    let mut bundle = secret_bundle(secret_name, version, value);
+    bundle["contentType"] = content_type.into();
+    bundle
+}
@@ -0,0 +1,161 @@
+use crate::KeyVaultError;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Longest secret name accepted by Key Vault.
+pub const MAX_SECRET_NAME_LENGTH: usize = 127;
+/// Most tags a secret version can carry.
+pub const MAX_TAGS: usize = 15;
+/// Longest tag name, in characters.
+pub const MAX_TAG_NAME_LENGTH: usize = 512;
+/// Longest tag value, in characters.
+pub const MAX_TAG_VALUE_LENGTH: usize = 256;
+/// Largest secret value, in bytes.
+pub const MAX_SECRET_VALUE_SIZE: usize = 25 * 1024;
+
+/// Why a secret was rejected before being sent to Key Vault.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SecretValidationError {
+    #[error("the name must be 1 to {} letters, digits or dashes", MAX_SECRET_NAME_LENGTH)]
+    InvalidName,
+
+    #[error("the version '{0}' is not a valid version identifier")]
+    InvalidVersion(String),
+
+    #[error("{0} tags are set, at most {} are allowed", MAX_TAGS)]
+    TooManyTags(usize),
+
+    #[error("the tag name '{0}' is longer than {} characters", MAX_TAG_NAME_LENGTH)]
+    TagNameTooLong(String),
+
+    #[error("the value of tag '{0}' is longer than {} characters", MAX_TAG_VALUE_LENGTH)]
+    TagValueTooLong(String),
+
+    #[error("the value is {0} bytes, larger than {} bytes", MAX_SECRET_VALUE_SIZE)]
+    ValueTooLarge(usize),
+}
+
+fn invalid(secret_name: &str, reason: SecretValidationError) -> KeyVaultError {
+    KeyVaultError::InvalidSecret {
+        secret_name: secret_name.to_owned(),
+        reason,
+    }
+}
+
+/// Checks that a secret name matches `^[0-9a-zA-Z-]{1,127}$`.
+pub fn validate_secret_name(secret_name: &str) -> Result<(), KeyVaultError> {
+    let valid = !secret_name.is_empty()
+        && secret_name.len() <= MAX_SECRET_NAME_LENGTH
+        && secret_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(invalid(secret_name, SecretValidationError::InvalidName))
+    }
+}
+
+/// Checks that a secret name is valid, and that a version is either empty (for the latest version)
+/// or a plain identifier which can not alter the request path.
+pub fn validate_secret_version(secret_name: &str, secret_version: &str) -> Result<(), KeyVaultError> {
+    validate_secret_name(secret_name)?;
+    if secret_version
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(invalid(
+            secret_name,
+            SecretValidationError::InvalidVersion(secret_version.to_owned()),
+        ))
+    }
+}
+
+/// Checks the number of tags of a secret version, and the length of their names and values.
+pub fn validate_tags(secret_name: &str, tags: &HashMap<String, String>) -> Result<(), KeyVaultError> {
+    if tags.len() > MAX_TAGS {
+        return Err(invalid(secret_name, SecretValidationError::TooManyTags(tags.len())));
+    }
+    for (name, value) in tags {
+        if name.chars().count() > MAX_TAG_NAME_LENGTH {
+            return Err(invalid(
+                secret_name,
+                SecretValidationError::TagNameTooLong(name.clone()),
+            ));
+        }
+        if value.chars().count() > MAX_TAG_VALUE_LENGTH {
+            return Err(invalid(
+                secret_name,
+                SecretValidationError::TagValueTooLong(name.clone()),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a secret value fits within Key Vault's size limit.
+pub fn validate_secret_value(secret_name: &str, value: &str) -> Result<(), KeyVaultError> {
+    if value.len() > MAX_SECRET_VALUE_SIZE {
+        Err(invalid(secret_name, SecretValidationError::ValueTooLarge(value.len())))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(result: Result<(), KeyVaultError>) -> SecretValidationError {
+        match result {
+            Err(KeyVaultError::InvalidSecret { reason, .. }) => reason,
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_secrets() {
+        assert!(validate_secret_name("db-Password-2").is_ok());
+        assert!(validate_secret_name(&"a".repeat(MAX_SECRET_NAME_LENGTH)).is_ok());
+        for name in &["", "db_password", "db/password", "../keys/db", "pässword"] {
+            assert_eq!(SecretValidationError::InvalidName, reason(validate_secret_name(name)));
+        }
+        assert_eq!(
+            SecretValidationError::InvalidName,
+            reason(validate_secret_name(&"a".repeat(MAX_SECRET_NAME_LENGTH + 1)))
+        );
+
+        assert!(validate_secret_version("db-password", "").is_ok());
+        assert!(validate_secret_version("db-password", "4387e9f3d6e14c459867679a90fd0f79").is_ok());
+        assert_eq!(
+            SecretValidationError::InvalidVersion("../other".to_owned()),
+            reason(validate_secret_version("db-password", "../other"))
+        );
+
+        let mut tags = (0..MAX_TAGS)
+            .map(|i| (format!("tag-{}", i), "value".to_owned()))
+            .collect::<HashMap<_, _>>();
+        assert!(validate_tags("db-password", &tags).is_ok());
+        tags.insert("one-too-many".to_owned(), "value".to_owned());
+        assert_eq!(
+            SecretValidationError::TooManyTags(MAX_TAGS + 1),
+            reason(validate_tags("db-password", &tags))
+        );
+
+        let mut tags = HashMap::new();
+        tags.insert("owner".to_owned(), "a".repeat(MAX_TAG_VALUE_LENGTH + 1));
+        assert_eq!(
+            SecretValidationError::TagValueTooLong("owner".to_owned()),
+            reason(validate_tags("db-password", &tags))
+        );
+
+        assert!(validate_secret_value("db-password", &"a".repeat(MAX_SECRET_VALUE_SIZE)).is_ok());
+        assert_eq!(
+            SecretValidationError::ValueTooLarge(MAX_SECRET_VALUE_SIZE + 1),
+            reason(validate_secret_value(
+                "db-password",
+                &"a".repeat(MAX_SECRET_VALUE_SIZE + 1)
+            ))
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use crate::secret::KeyVaultSecretBaseIdentifier;
+use crate::{KeyVaultClient, KeyVaultError};
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+
+/// A change to a secret, seen by [`watch_secrets`](KeyVaultClient::watch_secrets).
+#[derive(Debug, Clone)]
+pub enum SecretEvent {
+    /// A secret which did not exist before.
+    Created(KeyVaultSecretBaseIdentifier),
+    /// A new version of the secret became current.
+    ///
+    /// Listings do not include version identifiers, so a new version is told by the creation time of the
+    /// current version changing. A version created within the same second as the one it replaces is not
+    /// told apart from it, and is reported as [`Updated`](SecretEvent::Updated) at most. To know the
+    /// version itself, get the [versions](KeyVaultClient::get_secret_versions) of the secret.
+    NewVersion(KeyVaultSecretBaseIdentifier),
+    /// The properties of the current version changed, such as its tags or expiry.
+    Updated(KeyVaultSecretBaseIdentifier),
+    /// The current version was disabled.
+    Disabled(KeyVaultSecretBaseIdentifier),
+    /// The secret was deleted.
+    Deleted { secret_name: String },
+}
+
+impl SecretEvent {
+    /// The name of the secret the event is about.
+    pub fn secret_name(&self) -> &str {
+        match self {
+            SecretEvent::Created(secret)
+            | SecretEvent::NewVersion(secret)
+            | SecretEvent::Updated(secret)
+            | SecretEvent::Disabled(secret) => secret.name(),
+            SecretEvent::Deleted { secret_name } => secret_name,
+        }
+    }
+}
+
+type Snapshot = BTreeMap<String, KeyVaultSecretBaseIdentifier>;
+
+struct WatchState<'c, 'a, F> {
+    client: &'c mut KeyVaultClient<'a>,
+    filter: F,
+    interval: Duration,
+    previous: Option<Snapshot>,
+    polled: bool,
+    pending: VecDeque<SecretEvent>,
+}
+
+fn compare(previous: &Snapshot, current: &Snapshot) -> Vec<SecretEvent> {
+    let mut events = Vec::new();
+    for (name, secret) in current {
+        let event = match previous.get(name) {
+            None => SecretEvent::Created(secret.clone()),
+            Some(before) if *before.enabled() && !*secret.enabled() => SecretEvent::Disabled(secret.clone()),
+            // The listing does not include version identifiers, but the creation time is that of the current version.
+            Some(before) if before.time_created() != secret.time_created() => SecretEvent::NewVersion(secret.clone()),
+            Some(before) if before.time_updated() != secret.time_updated() => SecretEvent::Updated(secret.clone()),
+            Some(_) => continue,
+        };
+        events.push(event);
+    }
+    events.extend(
+        previous
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .map(|name| SecretEvent::Deleted {
+                secret_name: name.clone(),
+            }),
+    );
+    events
+}
+
+impl<'a> KeyVaultClient<'a> {
+    /// Watches the secrets accepted by `filter` for changes, by listing the secrets every `interval`
+    /// and comparing them with the previous listing.
+    ///
+    /// The first listing only sets the baseline, so the stream starts with the changes made after it.
+    /// A failed listing is yielded as an error, and the watch carries on at the next interval.
+    /// The stream never ends; drop it to stop watching.
+    /// This operation requires the secrets/list permission.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use azure_sdk_keyvault::KeyVaultClient;
+    /// use azure_sdk_keyvault::watch::SecretEvent;
+    /// use futures::StreamExt;
+    /// use std::time::Duration;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// async fn example() {
+    ///     let mut client = KeyVaultClient::new(
+    ///     &"CLIENT_ID",
+    ///     &"CLIENT_SECRET",
+    ///     &"TENANT_ID",
+    ///     &"KEYVAULT_NAME",
+    ///     );
+    ///     let mut events = client.watch_secrets(|s| s.name().starts_with("db-"), Duration::from_secs(60));
+    ///     while let Some(event) = events.next().await {
+    ///         match event {
+    ///             Ok(SecretEvent::NewVersion(secret)) => println!("Reloading {}", secret.name()),
+    ///             Ok(event) => println!("{:?}", event),
+    ///             Err(e) => eprintln!("Failed to list secrets: {}", e),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// Runtime::new().unwrap().block_on(example());
+    /// ```
+    pub fn watch_secrets<'c, F>(
+        &'c mut self,
+        filter: F,
+        interval: Duration,
+    ) -> BoxStream<'c, Result<SecretEvent, KeyVaultError>>
+    where
+        F: Fn(&KeyVaultSecretBaseIdentifier) -> bool + Send + 'c,
+    {
+        let state = WatchState {
+            client: self,
+            filter,
+            interval,
+            previous: None,
+            polled: false,
+            pending: VecDeque::new(),
+        };
+        stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(event) = state.pending.pop_front() {
+                    return Some((Ok(event), state));
+                }
+                if state.polled {
+                    tokio::time::delay_for(state.interval).await;
+                }
+                state.polled = true;
+
+                let secrets = match state.client.list_secrets().await {
+                    Ok(secrets) => secrets,
+                    Err(e) => return Some((Err(e), state)),
+                };
+                let current = secrets
+                    .into_iter()
+                    .filter(|secret| (state.filter)(secret))
+                    .map(|secret| (secret.name().clone(), secret))
+                    .collect::<Snapshot>();
+                if let Some(previous) = &state.previous {
+                    state.pending.extend(compare(previous, &current));
+                }
+                state.previous = Some(current);
+            }
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::API_VERSION;
+
+    use mockito::{mock, Matcher};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn listed(name: &str, enabled: bool, created: i64, updated: i64) -> serde_json::Value {
+        json!({
+            "id": format!("https://test-keyvault.vault.azure.net/secrets/{}", name),
+            "attributes": { "enabled": enabled, "created": created, "updated": updated }
+        })
+    }
+
+    #[tokio::test]
+    async fn watch_secrets() {
+        let listings = [
+            vec![
+                listed("db-a", true, 1, 1),
+                listed("db-b", true, 1, 1),
+                listed("db-c", true, 1, 1),
+                listed("db-e", true, 1, 1),
+                listed("other", true, 1, 1),
+            ],
+            vec![
+                listed("db-a", true, 1, 2),
+                listed("db-b", true, 2, 2),
+                listed("db-c", false, 1, 2),
+                listed("db-d", true, 2, 2),
+            ],
+        ]
+        .iter()
+        .map(|secrets| json!({ "value": secrets, "nextLink": null }).to_string())
+        .collect::<Vec<_>>();
+        let polls = AtomicUsize::new(0);
+        let _m = mock("GET", "/watch/secrets")
+            .match_query(Matcher::UrlEncoded("api-version".into(), API_VERSION.into()))
+            .with_header("content-type", "application/json")
+            .with_body_from_fn(move |w| {
+                let poll = polls.fetch_add(1, Ordering::SeqCst);
+                w.write_all(listings[poll.min(listings.len() - 1)].as_bytes())
+            })
+            .with_status(200)
+            .create();
+
+        let mut client = mock_client!(&"test-keyvault");
+        client.keyvault_endpoint = format!("{}/watch", mockito::server_url());
+
+        let events = client
+            .watch_secrets(|s| s.name().starts_with("db-"), Duration::from_millis(10))
+            .take(5)
+            .map(|event| event.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        let events = events
+            .iter()
+            .map(|event| {
+                let kind = match event {
+                    SecretEvent::Created(_) => "created",
+                    SecretEvent::NewVersion(_) => "new version",
+                    SecretEvent::Updated(_) => "updated",
+                    SecretEvent::Disabled(_) => "disabled",
+                    SecretEvent::Deleted { .. } => "deleted",
+                };
+                (event.secret_name(), kind)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("db-a", "updated"),
+                ("db-b", "new version"),
+                ("db-c", "disabled"),
+                ("db-d", "created"),
+                ("db-e", "deleted"),
+            ],
+            events
+        );
+    }
+}
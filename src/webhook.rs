@@ -0,0 +1,357 @@
+use crate::events::{KeyVaultEvent, KeyVaultEventEnvelope};
+use crate::KeyVaultError;
+use futures::future::{BoxFuture, FutureExt};
+use hyper::body::HttpBody;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use serde_json::json;
+use std::fmt;
+use std::future::Future;
+
+const SUBSCRIPTION_VALIDATION_EVENT: &str = "Microsoft.EventGrid.SubscriptionValidationEvent";
+const DEFAULT_SECRET_PARAMETER: &str = "key";
+/// Event Grid delivers batches of at most 1 MB.
+const MAX_DELIVERY_SIZE: usize = 1024 * 1024;
+
+type EventCallback = Box<dyn Fn(&KeyVaultEventEnvelope) + Send + Sync>;
+type AsyncEventCallback = Box<dyn Fn(KeyVaultEventEnvelope) -> BoxFuture<'static, ()> + Send + Sync>;
+type ErrorCallback = Box<dyn Fn(&KeyVaultError) + Send + Sync>;
+
+/// An HTTP handler receiving Key Vault events from an Event Grid subscription, to embed into a hyper server.
+///
+/// The handler completes the subscription validation handshake of both the Event Grid schema
+/// (a `SubscriptionValidationEvent`) and the CloudEvents schema (an `OPTIONS` request), and passes
+/// every delivered event to the callbacks. Asynchronous callbacks, such as those invalidating a
+/// [`SharedSecretCache`](crate::cache::SharedSecretCache), are awaited before the delivery is acknowledged. Events which can not be parsed are passed to the error callbacks
+/// instead, without failing the other events of their delivery. Deliveries larger than 1 MB are rejected.
+/// Requests are only accepted if they carry the shared secret as a query parameter, so the subscription
+/// endpoint must be configured as `https://{host}/{path}?key={shared secret}`.
+/// This module requires the `webhook` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// use azure_sdk_keyvault::KeyVaultClient;
+/// use azure_sdk_keyvault::cache::{CacheOptions, SecretCache};
+/// use azure_sdk_keyvault::events::KeyVaultEvent;
+/// use azure_sdk_keyvault::webhook::EventGridWebhook;
+/// use hyper::service::{make_service_fn, service_fn};
+/// use hyper::Server;
+/// use std::convert::Infallible;
+/// use std::sync::Arc;
+/// use tokio::runtime::Runtime;
+///
+/// async fn example() {
+///     let client = KeyVaultClient::new(
+///     &"CLIENT_ID",
+///     &"CLIENT_SECRET",
+///     &"TENANT_ID",
+///     &"KEYVAULT_NAME",
+///     );
+///     let cache = SecretCache::new(client, CacheOptions::default()).into_shared();
+///
+///     let invalidated = cache.clone();
+///     let webhook = Arc::new(EventGridWebhook::new("SHARED_SECRET").with_async_callback(move |envelope| {
+///         let cache = invalidated.clone();
+///         async move {
+///             if let KeyVaultEvent::SecretNewVersionCreated(data) = envelope.event() {
+///                 cache.lock().await.invalidate(data.object_name());
+///             }
+///         }
+///     }));
+///
+///     let make_service = make_service_fn(move |_| {
+///         let webhook = webhook.clone();
+///         async move {
+///             Ok::<_, Infallible>(service_fn(move |request| {
+///                 let webhook = webhook.clone();
+///                 async move { Ok::<_, Infallible>(webhook.handle(request).await) }
+///             }))
+///         }
+///     });
+///     Server::bind(&([0, 0, 0, 0], 8080).into()).serve(make_service).await.unwrap();
+/// }
+///
+/// Runtime::new().unwrap().block_on(example());
+/// ```
+pub struct EventGridWebhook {
+    shared_secret: String,
+    secret_parameter: String,
+    callbacks: Vec<EventCallback>,
+    async_callbacks: Vec<AsyncEventCallback>,
+    error_callbacks: Vec<ErrorCallback>,
+}
+
+impl fmt::Debug for EventGridWebhook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventGridWebhook")
+            .field("secret_parameter", &self.secret_parameter)
+            .field("callbacks", &self.callbacks.len())
+            .field("async_callbacks", &self.async_callbacks.len())
+            .field("error_callbacks", &self.error_callbacks.len())
+            .finish()
+    }
+}
+
+impl EventGridWebhook {
+    pub fn new(shared_secret: impl Into<String>) -> Self {
+        Self {
+            shared_secret: shared_secret.into(),
+            secret_parameter: DEFAULT_SECRET_PARAMETER.to_owned(),
+            callbacks: Vec::new(),
+            async_callbacks: Vec::new(),
+            error_callbacks: Vec::new(),
+        }
+    }
+
+    /// The query parameter holding the shared secret. Defaults to `key`.
+    pub fn with_secret_parameter(mut self, secret_parameter: impl Into<String>) -> Self {
+        self.secret_parameter = secret_parameter.into();
+        self
+    }
+
+    /// Adds a callback, called with every delivered event in order.
+    pub fn with_callback(mut self, callback: impl Fn(&KeyVaultEventEnvelope) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Adds a callback returning a future, called with every delivered event in order after the other callbacks.
+    /// Each future is awaited before the next event is passed on, and the delivery is only acknowledged
+    /// once they have all completed, so they should not take longer than Event Grid waits for a response.
+    pub fn with_async_callback<F>(
+        mut self,
+        callback: impl Fn(KeyVaultEventEnvelope) -> F + Send + Sync + 'static,
+    ) -> Self
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.async_callbacks
+            .push(Box::new(move |envelope| callback(envelope).boxed()));
+        self
+    }
+
+    /// Adds a callback, called with the error of every delivered event which could not be parsed.
+    pub fn with_error_callback(mut self, callback: impl Fn(&KeyVaultError) + Send + Sync + 'static) -> Self {
+        self.error_callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Handles a request from Event Grid.
+    pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if !self.is_authenticated(&request) {
+            return respond(StatusCode::UNAUTHORIZED, Body::empty());
+        }
+
+        match *request.method() {
+            Method::OPTIONS => {
+                // The CloudEvents abuse protection handshake.
+                match request.headers().get("WebHook-Request-Origin") {
+                    Some(origin) => Response::builder()
+                        .status(StatusCode::OK)
+                        .header("WebHook-Allowed-Origin", origin)
+                        .body(Body::empty())
+                        .unwrap(),
+                    None => respond(StatusCode::BAD_REQUEST, Body::empty()),
+                }
+            }
+            Method::POST => {
+                let mut body = request.into_body();
+                let mut delivery = Vec::new();
+                while let Some(chunk) = body.data().await {
+                    let chunk = match chunk {
+                        Ok(chunk) => chunk,
+                        Err(_) => return respond(StatusCode::BAD_REQUEST, Body::empty()),
+                    };
+                    if delivery.len() + chunk.len() > MAX_DELIVERY_SIZE {
+                        return respond(StatusCode::PAYLOAD_TOO_LARGE, Body::empty());
+                    }
+                    delivery.extend_from_slice(&chunk);
+                }
+                self.handle_delivery(&delivery).await
+            }
+            _ => respond(StatusCode::METHOD_NOT_ALLOWED, Body::empty()),
+        }
+    }
+
+    async fn handle_delivery(&self, body: &[u8]) -> Response<Body> {
+        let parsed = match std::str::from_utf8(body)
+            .ok()
+            .and_then(|body| KeyVaultEventEnvelope::parse_each(body).ok())
+        {
+            Some(parsed) => parsed,
+            None => return respond(StatusCode::BAD_REQUEST, Body::empty()),
+        };
+        let mut events = Vec::with_capacity(parsed.len());
+        for event in parsed {
+            match event {
+                Ok(envelope) => events.push(envelope),
+                Err(e) => {
+                    for callback in &self.error_callbacks {
+                        callback(&e);
+                    }
+                }
+            }
+        }
+
+        for envelope in &events {
+            if let KeyVaultEvent::Other { event_type, data } = envelope.event() {
+                if event_type == SUBSCRIPTION_VALIDATION_EVENT {
+                    return match data.get("validationCode") {
+                        Some(code) => respond(
+                            StatusCode::OK,
+                            Body::from(json!({ "validationResponse": code }).to_string()),
+                        ),
+                        None => respond(StatusCode::BAD_REQUEST, Body::empty()),
+                    };
+                }
+            }
+        }
+        for envelope in &events {
+            for callback in &self.callbacks {
+                callback(envelope);
+            }
+            for callback in &self.async_callbacks {
+                callback(envelope.clone()).await;
+            }
+        }
+        respond(StatusCode::OK, Body::empty())
+    }
+
+    fn is_authenticated(&self, request: &Request<Body>) -> bool {
+        let query = request.uri().query().unwrap_or_default();
+        url::form_urlencoded::parse(query.as_bytes()).any(|(name, value)| {
+            name == self.secret_parameter.as_str() && constant_time_eq(&value, &self.shared_secret)
+        })
+    }
+}
+
+fn respond(status: StatusCode, body: Body) -> Response<Body> {
+    Response::builder().status(status).body(body).unwrap()
+}
+
+/// Compares two strings in a time which does not depend on where they differ.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    async fn post(webhook: &EventGridWebhook, uri: &str, body: serde_json::Value) -> (StatusCode, String) {
+        let request = Request::post(uri).body(Body::from(body.to_string())).unwrap();
+        let response = webhook.handle(request).await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn receive_events() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let async_received = Arc::new(Mutex::new(Vec::new()));
+        let async_sink = async_received.clone();
+        let webhook = EventGridWebhook::new("SHARED_SECRET")
+            .with_callback(move |envelope| {
+                if let Some(identifier) = envelope.event().identifier() {
+                    sink.lock().unwrap().push(identifier.name().clone());
+                }
+            })
+            .with_async_callback(move |envelope| {
+                let sink = async_sink.clone();
+                async move {
+                    tokio::time::delay_for(std::time::Duration::from_millis(1)).await;
+                    sink.lock().unwrap().push(envelope.id().clone());
+                }
+            });
+
+        let validation = json!([{
+            "id": "EVENT_1",
+            "topic": "/subscriptions/SUBSCRIPTION_ID",
+            "subject": "",
+            "eventType": SUBSCRIPTION_VALIDATION_EVENT,
+            "eventTime": "2020-09-13T12:26:40Z",
+            "data": { "validationCode": "VALIDATION_CODE" }
+        }]);
+        let (status, body) = post(&webhook, "/events?key=SHARED_SECRET", validation.clone()).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(json!({ "validationResponse": "VALIDATION_CODE" }).to_string(), body);
+
+        let (status, _) = post(&webhook, "/events?key=WRONG_SECRET", validation.clone()).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, status);
+        let (status, _) = post(&webhook, "/events", validation).await;
+        assert_eq!(StatusCode::UNAUTHORIZED, status);
+
+        let request = Request::options("/events?key=SHARED_SECRET")
+            .header("WebHook-Request-Origin", "eventgrid.azure.net")
+            .body(Body::empty())
+            .unwrap();
+        let response = webhook.handle(request).await;
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("eventgrid.azure.net", response.headers()["WebHook-Allowed-Origin"]);
+
+        let delivery = json!([{
+            "specversion": "1.0",
+            "id": "EVENT_2",
+            "source": "/subscriptions/SUBSCRIPTION_ID/providers/Microsoft.KeyVault/vaults/test-keyvault",
+            "subject": "test-secret",
+            "type": "Microsoft.KeyVault.SecretNewVersionCreated",
+            "time": "2020-09-13T12:26:40Z",
+            "data": {
+                "Id": "https://test-keyvault.vault.azure.net/secrets/test-secret/VERSION_2",
+                "VaultName": "test-keyvault",
+                "ObjectType": "Secret",
+                "ObjectName": "test-secret",
+                "Version": "VERSION_2"
+            }
+        }]);
+        let (status, _) = post(&webhook, "/events?key=SHARED_SECRET", delivery).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(vec!["test-secret".to_owned()], *received.lock().unwrap());
+        assert_eq!(vec!["EVENT_2".to_owned()], *async_received.lock().unwrap());
+
+        let request = Request::post("/events?key=SHARED_SECRET")
+            .body(Body::from("not json"))
+            .unwrap();
+        assert_eq!(StatusCode::BAD_REQUEST, webhook.handle(request).await.status());
+    }
+
+    #[tokio::test]
+    async fn skip_malformed_events() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let errors = Arc::new(Mutex::new(0));
+        let (sink, error_sink) = (received.clone(), errors.clone());
+        let webhook = EventGridWebhook::new("SHARED_SECRET")
+            .with_callback(move |envelope| sink.lock().unwrap().push(envelope.id().clone()))
+            .with_error_callback(move |_| *error_sink.lock().unwrap() += 1);
+
+        let delivery = json!([
+            { "id": "EVENT_1" },
+            {
+                "id": "EVENT_2",
+                "topic": "/subscriptions/SUBSCRIPTION_ID/providers/Microsoft.KeyVault/vaults/test-keyvault",
+                "subject": "test-secret",
+                "eventType": "Microsoft.KeyVault.SecretNearExpiry",
+                "eventTime": "2020-09-13T12:26:40Z",
+                "data": {
+                    "Id": "https://test-keyvault.vault.azure.net/secrets/test-secret/VERSION_1",
+                    "VaultName": "test-keyvault",
+                    "ObjectType": "Secret",
+                    "ObjectName": "test-secret",
+                    "Version": "VERSION_1"
+                }
+            }
+        ]);
+        let (status, _) = post(&webhook, "/events?key=SHARED_SECRET", delivery).await;
+        assert_eq!(StatusCode::OK, status);
+        assert_eq!(vec!["EVENT_2".to_owned()], *received.lock().unwrap());
+        assert_eq!(1, *errors.lock().unwrap());
+
+        let oversized = Request::post("/events?key=SHARED_SECRET")
+            .body(Body::from(vec![b' '; MAX_DELIVERY_SIZE + 1]))
+            .unwrap();
+        assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, webhook.handle(oversized).await.status());
+    }
+}